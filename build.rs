@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use eg_font_converter::FontConverter;
 
 fn main() {
@@ -19,9 +21,68 @@ fn main() {
             .expect("saving mono font");
     }
 
-    linker_be_nice();
-    // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
-    println!("cargo:rustc-link-arg=-Tlinkall.x");
+    check_locale_glyphs(fonts.map(|(path, _)| path));
+
+    // Host builds for tests link without the ESP32 linker scripts.
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("none") {
+        linker_be_nice();
+        // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
+        println!("cargo:rustc-link-arg=-Tlinkall.x");
+    }
+}
+
+/// Fails the build if a string literal in `src/locale.rs` contains a character that is missing from
+/// one of the fonts and would otherwise silently not be drawn.
+fn check_locale_glyphs(font_paths: [&str; 3]) {
+    const LOCALE_PATH: &str = "src/locale.rs";
+
+    println!("cargo::rerun-if-changed={LOCALE_PATH}");
+
+    let source = std::fs::read_to_string(LOCALE_PATH).expect("reading locale source");
+    let literals = string_literals(&source);
+
+    for path in font_paths {
+        let bdf = std::fs::read_to_string(path).expect("reading BDF font");
+
+        let encodings: HashSet<u32> = bdf
+            .lines()
+            .filter_map(|line| line.strip_prefix("ENCODING "))
+            .filter_map(|encoding| encoding.trim().parse().ok())
+            .collect();
+
+        for literal in &literals {
+            if let Some(missing) = literal
+                .chars()
+                .find(|c| !encodings.contains(&u32::from(*c)))
+            {
+                panic!("{path} has no glyph for {missing:?} used in {literal:?}");
+            }
+        }
+    }
+}
+
+/// Returns the contents of all single line string literals outside of line comments.
+fn string_literals(source: &str) -> Vec<&str> {
+    let mut literals = Vec::new();
+
+    for line in source.lines() {
+        if line.trim_start().starts_with("//") {
+            continue;
+        }
+
+        let mut rest = line;
+
+        while let Some((_, after_quote)) = rest.split_once('"') {
+            let Some((literal, after_literal)) = after_quote.split_once('"') else {
+                break;
+            };
+
+            literals.push(literal);
+            rest = after_literal;
+        }
+    }
+
+    literals
 }
 
 fn linker_be_nice() {
@@ -0,0 +1,228 @@
+use embedded_io_async::Read;
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::{String, format};
+use reqwless::{client::HttpClient, request::Method};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+use crate::json::Tokenizer;
+use crate::weather::Coordinates;
+
+/// Pollen types reported by the Open-Meteo air quality API.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pollen {
+    Alder,
+    Birch,
+    Grass,
+    Ragweed,
+}
+
+impl Pollen {
+    const ALL: [Pollen; 4] = [Pollen::Alder, Pollen::Birch, Pollen::Grass, Pollen::Ragweed];
+
+    fn variable(self) -> &'static str {
+        match self {
+            Pollen::Alder => "alder_pollen",
+            Pollen::Birch => "birch_pollen",
+            Pollen::Grass => "grass_pollen",
+            Pollen::Ragweed => "ragweed_pollen",
+        }
+    }
+
+    /// Returns the burden level between 0 (none) and 3 (high) for a concentration given in
+    /// grains/m³.
+    pub fn level(self, concentration: f32) -> u8 {
+        let thresholds = match self {
+            Pollen::Alder | Pollen::Grass => [1.0, 10.0, 50.0],
+            Pollen::Birch => [1.0, 15.0, 90.0],
+            Pollen::Ragweed => [1.0, 5.0, 20.0],
+        };
+
+        thresholds
+            .into_iter()
+            .filter(|threshold| concentration >= *threshold)
+            .count() as u8
+    }
+}
+
+/// Categories of the European Air Quality Index.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AqiCategory {
+    Good,
+    Fair,
+    Moderate,
+    Poor,
+    VeryPoor,
+    ExtremelyPoor,
+}
+
+impl AqiCategory {
+    fn from_index(index: f32) -> Self {
+        match index {
+            ..20.0 => AqiCategory::Good,
+            ..40.0 => AqiCategory::Fair,
+            ..60.0 => AqiCategory::Moderate,
+            ..80.0 => AqiCategory::Poor,
+            ..100.0 => AqiCategory::VeryPoor,
+            _ => AqiCategory::ExtremelyPoor,
+        }
+    }
+}
+
+/// Daily maxima of the European AQI and pollen concentrations.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AirQuality {
+    pub european_aqi: f32,
+    /// Concentrations in grains/m³, indexed like [`Pollen::ALL`].
+    pollen: [f32; 4],
+}
+
+impl AirQuality {
+    pub fn category(&self) -> AqiCategory {
+        AqiCategory::from_index(self.european_aqi)
+    }
+
+    /// Returns the pollen types present today with their level, highest first.
+    pub fn highest_pollen(&self) -> heapless::Vec<(Pollen, u8), 4> {
+        let mut pollen: heapless::Vec<_, 4> = Pollen::ALL
+            .into_iter()
+            .zip(self.pollen)
+            .map(|(pollen, concentration)| (pollen, pollen.level(concentration)))
+            .filter(|(_, level)| *level > 0)
+            .collect();
+
+        pollen.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        pollen
+    }
+}
+
+/// Fetch today's air quality and pollen forecast at `coordinates` from Open-Meteo.
+pub async fn today<T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+) -> Result<AirQuality, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    log::debug!("getting air quality");
+
+    let url: String<256> = format!(
+        "https://air-quality-api.open-meteo.com/v1/air-quality?latitude={:.4}&longitude={:.4}&hourly=european_aqi,alder_pollen,birch_pollen,grass_pollen,ragweed_pollen&timezone=Europe%2FBerlin&forecast_days=1",
+        coordinates.latitude,
+        coordinates.longitude
+    )
+    .map_err(|_| Error::Http("air quality URL too long"))?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to air quality URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse(response.body().reader()).await
+}
+
+async fn parse<R: Read>(reader: R) -> Result<AirQuality, Error> {
+    let mut air_quality = AirQuality::default();
+    let mut values = [0.0; 24];
+
+    let mut json = Tokenizer::<_>::new(reader);
+    json.seek_key("hourly").await?;
+    json.begin_object().await?;
+
+    while let Some(key) = json.next_key().await? {
+        let target = if key == "european_aqi" {
+            &mut air_quality.european_aqi
+        } else if let Some(index) = Pollen::ALL
+            .iter()
+            .position(|pollen| pollen.variable() == key)
+        {
+            &mut air_quality.pollen[index]
+        } else {
+            json.skip_value().await?;
+            continue;
+        };
+
+        let count = json.read_array(&mut values, |token| token.as_f32()).await?;
+
+        // Missing values are NaN which `f32::max` ignores.
+        *target = values[..count].iter().copied().fold(0.0, f32::max);
+    }
+
+    Ok(air_quality)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &[u8] = include_bytes!("../fixtures/open_meteo_air_quality.json");
+
+    #[test]
+    fn parses_daily_maxima() {
+        let air_quality = embassy_futures::block_on(parse(RESPONSE)).unwrap();
+
+        assert_eq!(air_quality.european_aqi, 45.0);
+        // Ragweed has no values at all.
+        assert_eq!(air_quality.pollen, [12.4, 0.9, 55.3, 0.0]);
+        assert_eq!(air_quality.category(), AqiCategory::Moderate);
+        assert_eq!(
+            air_quality.highest_pollen().as_slice(),
+            [(Pollen::Grass, 3), (Pollen::Alder, 2)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_responses() {
+        let parse = |response: &[u8]| embassy_futures::block_on(parse(response));
+
+        assert!(parse(b"{\"error\":true,\"reason\":\"Cannot initialize\"}").is_err());
+        assert!(parse(b"{\"hourly\":{\"european_aqi\":[\"high\"]}}").is_err());
+        assert!(parse(b"{\"hourly\":[]}").is_err());
+    }
+
+    #[test]
+    fn maps_concentrations_to_levels() {
+        for (pollen, levels) in [
+            (Pollen::Alder, [0.9, 1.0, 10.0, 50.0]),
+            (Pollen::Birch, [0.9, 1.0, 15.0, 90.0]),
+            (Pollen::Grass, [0.9, 1.0, 10.0, 50.0]),
+            (Pollen::Ragweed, [0.9, 1.0, 5.0, 20.0]),
+        ] {
+            for (level, concentration) in levels.into_iter().enumerate() {
+                assert_eq!(
+                    pollen.level(concentration),
+                    level as u8,
+                    "{pollen:?} {concentration}"
+                );
+            }
+
+            assert_eq!(pollen.level(f32::NAN), 0);
+            assert_eq!(pollen.level(1000.0), 3);
+        }
+    }
+
+    #[test]
+    fn maps_index_to_category() {
+        for (index, category) in [
+            (0.0, AqiCategory::Good),
+            (19.9, AqiCategory::Good),
+            (20.0, AqiCategory::Fair),
+            (40.0, AqiCategory::Moderate),
+            (60.0, AqiCategory::Poor),
+            (80.0, AqiCategory::VeryPoor),
+            (99.9, AqiCategory::VeryPoor),
+            (100.0, AqiCategory::ExtremelyPoor),
+            (250.0, AqiCategory::ExtremelyPoor),
+        ] {
+            assert_eq!(AqiCategory::from_index(index), category, "{index}");
+        }
+    }
+}
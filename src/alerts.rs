@@ -0,0 +1,284 @@
+use core::str::FromStr;
+
+use embedded_io_async::Read;
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::{String, format};
+use reqwless::{client::HttpClient, request::Method};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+use crate::json::{Token, Tokenizer};
+use crate::locale::Locale;
+use crate::weather::Coordinates;
+
+const MAX_HEADLINE_LENGTH: usize = 64;
+
+/// Severity levels of DWD warnings in ascending order.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    #[default]
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+}
+
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minor" => Ok(Self::Minor),
+            "moderate" => Ok(Self::Moderate),
+            "severe" => Ok(Self::Severe),
+            "extreme" => Ok(Self::Extreme),
+            _ => Err(Error::Config(
+                "unknown alert severity, expected minor, moderate, severe or extreme",
+            )),
+        }
+    }
+}
+
+/// An official weather warning.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Alert {
+    pub severity: Severity,
+    pub headline: String<MAX_HEADLINE_LENGTH>,
+    #[serde(with = "crate::cache::fixed_offset::option")]
+    pub onset: Option<jiff::Zoned>,
+    /// End of validity or `None` if the warning is valid until further notice.
+    #[serde(with = "crate::cache::fixed_offset::option")]
+    pub expires: Option<jiff::Zoned>,
+}
+
+impl Alert {
+    /// Returns whether the warning is valid at `now`.
+    pub fn is_active(&self, now: &jiff::Zoned) -> bool {
+        self.onset.as_ref().is_none_or(|onset| onset <= now)
+            && self.expires.as_ref().is_none_or(|expires| expires > now)
+    }
+}
+
+/// Fetch DWD warnings for `coordinates` from [Bright Sky](https://brightsky.dev) and
+/// return the most severe one active at `now` with at least `min_severity`. Headlines are German
+/// for [`Locale::De`] and English otherwise.
+pub async fn most_severe<T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    min_severity: Severity,
+    locale: Locale,
+) -> Result<Option<Alert>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    log::debug!("getting weather alerts");
+
+    let url: String<128> = format!(
+        "https://api.brightsky.dev/alerts?lat={:.4}&lon={:.4}&tz=Europe%2FBerlin",
+        coordinates.latitude, coordinates.longitude
+    )
+    .map_err(|_| Error::Http("alerts URL too long"))?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to alerts URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse(response.body().reader(), now, min_severity, locale).await
+}
+
+async fn parse<R: Read>(
+    reader: R,
+    now: &jiff::Zoned,
+    min_severity: Severity,
+    locale: Locale,
+) -> Result<Option<Alert>, Error> {
+    let headline_key = match locale {
+        Locale::De => "headline_de",
+        _ => "headline_en",
+    };
+
+    let mut json = Tokenizer::<_, 128>::new(reader);
+    let mut most_severe: Option<Alert> = None;
+
+    json.seek_key("alerts").await?;
+    json.begin_array().await?;
+
+    loop {
+        match json.next().await? {
+            Token::BeginObject => {}
+            Token::EndArray => return Ok(most_severe),
+            _ => return Err(Error::ParseJson("expected alert object")),
+        }
+
+        let mut alert = Alert::default();
+
+        while let Some(key) = json.next_key().await? {
+            match key {
+                "severity" => {
+                    alert.severity = json
+                        .next()
+                        .await?
+                        .as_str()?
+                        .parse()
+                        .map_err(|_| Error::ParseJson("unknown alert severity"))?
+                }
+                key if key == headline_key => {
+                    for c in json.next().await?.as_str()?.chars() {
+                        if alert.headline.push(c).is_err() {
+                            break;
+                        }
+                    }
+                }
+                "onset" => alert.onset = parse_time(json.next().await?, now)?,
+                "expires" => alert.expires = parse_time(json.next().await?, now)?,
+                _ => json.skip_value().await?,
+            }
+        }
+
+        if alert.severity >= min_severity
+            && alert.is_active(now)
+            && most_severe
+                .as_ref()
+                .is_none_or(|current| alert.severity > current.severity)
+        {
+            most_severe = Some(alert);
+        }
+    }
+}
+
+/// Parse an optional timestamp into the time zone of `now`.
+fn parse_time(token: Token<'_>, now: &jiff::Zoned) -> Result<Option<jiff::Zoned>, Error> {
+    if token == Token::Null {
+        return Ok(None);
+    }
+
+    let timestamp: jiff::Timestamp = token
+        .as_str()?
+        .parse()
+        .map_err(|_| Error::DateTime("failed to parse alert time"))?;
+
+    Ok(Some(timestamp.to_zoned(now.time_zone().clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use jiff::tz::TimeZone;
+
+    const RESPONSE: &[u8] = include_bytes!("../fixtures/bright_sky_alerts.json");
+
+    fn time(day: i8, hour: i8) -> jiff::Zoned {
+        jiff::civil::date(2025, 1, day)
+            .at(hour, 0, 0, 0)
+            .to_zoned(TimeZone::fixed(jiff::tz::offset(1)))
+            .unwrap()
+    }
+
+    fn most_severe(now: jiff::Zoned, min_severity: Severity, locale: Locale) -> Option<Alert> {
+        embassy_futures::block_on(parse(RESPONSE, &now, min_severity, locale)).unwrap()
+    }
+
+    #[test]
+    fn picks_most_severe_active_alert() {
+        let alert = most_severe(time(15, 21), Severity::Minor, Locale::En).unwrap();
+
+        // The extreme warning has expired and the severe one has not started yet.
+        assert_eq!(alert.severity, Severity::Moderate);
+        assert_eq!(alert.headline, "Official WARNING of BLACK ICE");
+        assert_eq!(alert.onset, Some(time(15, 20)));
+        assert_eq!(alert.expires, Some(time(16, 8)));
+
+        let alert = most_severe(time(15, 19), Severity::Minor, Locale::En).unwrap();
+
+        assert_eq!(alert.severity, Severity::Minor);
+    }
+
+    #[test]
+    fn filters_by_minimum_severity() {
+        let severity = |min_severity| {
+            most_severe(time(15, 19), min_severity, Locale::En).map(|alert| alert.severity)
+        };
+
+        assert_eq!(severity(Severity::Minor), Some(Severity::Minor));
+        assert_eq!(severity(Severity::Moderate), None);
+
+        let severity = |min_severity| {
+            most_severe(time(15, 21), min_severity, Locale::En).map(|alert| alert.severity)
+        };
+
+        assert_eq!(severity(Severity::Moderate), Some(Severity::Moderate));
+        assert_eq!(severity(Severity::Severe), None);
+        assert_eq!(severity(Severity::Extreme), None);
+    }
+
+    #[test]
+    fn alerts_are_active_from_onset_until_expiry() {
+        let severity =
+            |now| most_severe(now, Severity::Severe, Locale::En).map(|alert| alert.severity);
+
+        assert_eq!(severity(time(16, 11)), None);
+        assert_eq!(severity(time(16, 12)), Some(Severity::Severe));
+        assert_eq!(severity(time(16, 19)), Some(Severity::Severe));
+        assert_eq!(severity(time(16, 20)), None);
+
+        let alert = Alert {
+            onset: Some(time(16, 12)),
+            expires: Some(time(16, 20)),
+            ..Alert::default()
+        };
+
+        assert!(!alert.is_active(&time(16, 11)));
+        assert!(alert.is_active(&time(16, 12)));
+        assert!(!alert.is_active(&time(16, 20)));
+
+        let until_further_notice = Alert {
+            expires: None,
+            ..alert
+        };
+
+        assert!(until_further_notice.is_active(&time(31, 0)));
+        assert!(Alert::default().is_active(&time(15, 0)));
+    }
+
+    #[test]
+    fn chooses_headline_by_locale() {
+        let headline = |locale| {
+            most_severe(time(16, 12), Severity::Minor, locale)
+                .unwrap()
+                .headline
+        };
+
+        assert_eq!(
+            headline(Locale::De),
+            "Amtliche UNWETTERWARNUNG vor STURMBÖEN"
+        );
+
+        for locale in [Locale::En, Locale::Fr, Locale::Nl, Locale::Es] {
+            assert_eq!(headline(locale), "Official WARNING of GALE-FORCE GUSTS");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_responses() {
+        let parse = |response: &[u8]| {
+            embassy_futures::block_on(parse(response, &time(15, 21), Severity::Minor, Locale::En))
+        };
+
+        assert!(parse(b"{\"alerts\":[]}").unwrap().is_none());
+        assert!(parse(b"{\"alerts\":[{\"severity\":\"unknown\"}]}").is_err());
+        assert!(parse(b"{\"alerts\":[{\"onset\":\"tomorrow\"}]}").is_err());
+        assert!(parse(b"{\"alerts\":[1]}").is_err());
+        assert!(parse(b"{\"error\":\"not found\"}").is_err());
+    }
+}
@@ -3,35 +3,72 @@
 #![deny(clippy::mem_forget)]
 #![deny(clippy::large_stack_frames)]
 
+use core::cell::RefCell;
+
 use embassy_executor::Spawner;
 use embassy_futures::join;
+use embassy_futures::select::{Either, select};
 use embassy_net::tcp::client::{TcpClient, TcpClientState};
 use embassy_net::{DhcpConfig, dns::DnsSocket};
-use embassy_time::{Delay, Duration, Timer};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Delay, Duration, Instant, Timer};
+use embedded_graphics::draw_target::DrawTargetExt;
 use embedded_graphics::prelude::DrawTarget;
 use embedded_hal_bus::spi::ExclusiveDevice;
+use embedded_storage::nor_flash::NorFlash;
 use epd_waveshare::epd7in5_v2::{Display7in5, Epd7in5};
 use epd_waveshare::prelude::*;
 use esp_backtrace as _;
+use esp_bootloader_esp_idf::partitions::{self, DataPartitionSubType, FlashRegion, PartitionType};
+use esp_hal::Async;
 use esp_hal::clock::CpuClock;
 use esp_hal::gpio::{Input, InputConfig, Level, Output, OutputConfig};
 use esp_hal::rng::Rng;
 use esp_hal::spi::Mode;
 use esp_hal::spi::master::{Config, Spi};
 use esp_hal::timer::timg::TimerGroup;
+use esp_storage::FlashStorage;
 use reqwless::client::{HttpClient, TlsConfig};
-
-use esp_home_dashboard::{clock, ics, ntp, todo, ui, weather, wifi};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use esp_home_dashboard::cache::Cached;
+use esp_home_dashboard::config::Config;
+use esp_home_dashboard::errors::Error;
+use esp_home_dashboard::locale::Locale;
+use esp_home_dashboard::schedule::Scheduler;
+use esp_home_dashboard::status::{Refresh, Source};
+use esp_home_dashboard::storage::Store;
+use esp_home_dashboard::{
+    air_quality, alerts, clock, frame, ics, mdns, ntp, portal, server, todo, ui, weather, wifi,
+};
 
 esp_bootloader_esp_idf::esp_app_desc!();
 
-const WIFI_SSID: &str = env!("WIFI_SSID");
-const WIFI_PASSWORD: &str = env!("WIFI_PASSWORD");
-const ICAL_URL: &str = env!("ICAL_URL");
 const TIMEZONE_DATA_EUROPE_BERLIN: &[u8] = include_bytes!("/usr/share/zoneinfo/Europe/Berlin");
-const NTP_HOST_NAME: Option<&str> = option_env!("NTP_HOST_NAME");
-const TODO_URL: Option<&str> = option_env!("TODO_URL");
-const TODO_AUTHORIZATION_HEADER: Option<&str> = option_env!("TODO_AUTHORIZATION_HEADER");
+
+/// Number of hours and days to request hourly and daily weather forecasts for.
+const FORECAST_HOURS: usize = 48;
+const FORECAST_DAYS: usize = 4;
+
+/// Maximum number of upcoming calendar events kept.
+const MAX_EVENTS: usize = 10;
+
+/// Time after which the device restarts when starting up failed.
+const BOOT_ERROR_RESTART_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// SPI device and driver of the e-paper display.
+type EpdSpi = ExclusiveDevice<Spi<'static, Async>, Output<'static>, Delay>;
+type Epd = Epd7in5<EpdSpi, Input<'static>, Output<'static>, Output<'static>, Delay>;
+
+/// Weather providers in order of preference, later ones are only asked if earlier ones fail.
+const WEATHER_PROVIDERS: [weather::Provider; 3] = [
+    weather::Provider::OpenMeteo,
+    weather::Provider::BrightSky,
+    weather::Provider::MetNorway,
+];
 
 #[allow(clippy::large_stack_frames)]
 #[esp_rtos::main]
@@ -43,6 +80,7 @@ async fn main(_spawner: Spawner) -> ! {
     let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
     let peripherals = esp_hal::init(config);
 
+    // Without the display, errors up to creating it can only be logged.
     let spi = Spi::new(
         peripherals.SPI2,
         Config::default()
@@ -74,16 +112,49 @@ async fn main(_spawner: Spawner) -> ! {
         .await
         .expect("creating EPD");
 
+    let mut flash = FlashStorage::new(peripherals.FLASH);
+    let mut partition_table = [0; partitions::PARTITION_TABLE_MAX_LEN];
+
+    // Keep running without storage, data is then only fetched again after a restart.
+    let mut store = open_store(&mut flash, &mut partition_table)
+        .inspect_err(|err| log::error!("failed to open storage: {err:?}"))
+        .ok();
+
+    // Without usable settings, only the setup portal is started to enter them.
+    let (config, configured) = match load_config(&mut store) {
+        Ok(config) => (config, true),
+        Err(err) => {
+            log::error!("no valid configuration: {err:?}");
+            (Config::default(), false)
+        }
+    };
+
+    let store = RefCell::new(store);
+
     let mut display = Display7in5::default();
 
     display.set_rotation(DisplayRotation::Rotate90);
 
-    let radio_init = esp_radio::init().expect("initializing Wi-Fi/BLE controller");
+    let locale = config.locale;
+
+    let radio_init = match esp_radio::init() {
+        Ok(radio_init) => radio_init,
+        Err(_) => {
+            let error = Error::Wifi("failed to initialize Wi-Fi/BLE controller");
+            show_boot_error(&mut epd, &mut spi, &mut display, error, locale).await
+        }
+    };
 
     let (wifi_controller, interfaces) =
-        esp_radio::wifi::new(&radio_init, peripherals.WIFI, Default::default())
-            .expect("initializing Wi-Fi controller");
+        match esp_radio::wifi::new(&radio_init, peripherals.WIFI, Default::default()) {
+            Ok(wifi) => wifi,
+            Err(_) => {
+                let error = Error::Wifi("failed to initialize Wi-Fi controller");
+                show_boot_error(&mut epd, &mut spi, &mut display, error, locale).await
+            }
+        };
     let wifi_device = interfaces.sta;
+    let access_point_device = interfaces.ap;
 
     let rng = Rng::new();
     let net_seed = rng.random() as u64 | ((rng.random() as u64) << 32);
@@ -95,24 +166,65 @@ async fn main(_spawner: Spawner) -> ! {
 
     let tls_seed = rng.random() as u64 | ((rng.random() as u64) << 32);
 
-    let timezone = jiff::tz::TimeZone::tzif("Europe/Berlin", TIMEZONE_DATA_EUROPE_BERLIN)
-        .expect("parsing timezone data");
+    let timezone = match jiff::tz::TimeZone::tzif("Europe/Berlin", TIMEZONE_DATA_EUROPE_BERLIN) {
+        Ok(timezone) => timezone,
+        Err(_) => {
+            let error = Error::DateTime("failed to parse timezone data");
+            show_boot_error(&mut epd, &mut spi, &mut display, error, locale).await
+        }
+    };
+
+    let display = Mutex::<NoopRawMutex, _>::new(display);
 
     let clock = clock::Clock::new(timezone.clone());
 
+    let signal_strength = wifi::SignalStrength::default();
+
+    let alert_min_severity = config.alert_min_severity;
+
+    let units = config.units;
+
+    let layout = config.layout();
+
     // Careful: this needs to cover _all_ sockets we want to use.
-    let mut resources = embassy_net::StackResources::<4>::new();
+    let mut resources = embassy_net::StackResources::<6>::new();
 
     let (net_stack, mut net_runner) =
         embassy_net::new(wifi_device, net_config, &mut resources, net_seed);
 
-    let sync_time = ntp::sync(
-        &net_stack,
-        NTP_HOST_NAME.unwrap_or("de.pool.ntp.org"),
-        clock.clone(),
+    let mut access_point_resources = embassy_net::StackResources::<3>::new();
+    let access_point_seed = rng.random() as u64 | ((rng.random() as u64) << 32);
+
+    let (access_point_stack, mut access_point_runner) = embassy_net::new(
+        access_point_device,
+        portal::net_config(),
+        &mut access_point_resources,
+        access_point_seed,
     );
 
+    let refresh = RefCell::new(Refresh::default());
+    let refresh_requested = Signal::<NoopRawMutex, ()>::new();
+
+    let sync_time = ntp::sync(&net_stack, &config.ntp_host_name, clock.clone());
+    let mdns_responder = mdns::serve(net_stack, &config.host_name);
+
     let main_logic = async {
+        let mut frames = frame::FrameHistory::default();
+
+        let mut scheduler = Scheduler::new(|source| match source {
+            Source::Todos if config.todo().is_none() => None,
+            source => Some(fetch_interval(source)),
+        });
+
+        // Data of the last successful fetch of each source, starting with what was stored before
+        // the last restart.
+        let mut hourly_forecast = load(&mut store.borrow_mut(), Source::HourlyWeather);
+        let mut alert = load(&mut store.borrow_mut(), Source::Alerts);
+        let mut daily_forecast = load(&mut store.borrow_mut(), Source::DailyWeather);
+        let mut air_quality = load(&mut store.borrow_mut(), Source::AirQuality);
+        let mut todos = load(&mut store.borrow_mut(), Source::Todos);
+        let mut events = load(&mut store.borrow_mut(), Source::Events);
+
         loop {
             log::debug!("waiting for link");
 
@@ -132,89 +244,359 @@ async fn main(_spawner: Spawner) -> ! {
 
             let mut client = HttpClient::new_with_tls(&tcp, &dns, tls_config);
 
+            let now = Instant::now();
+
+            if scheduler.is_due(Source::HourlyWeather, now) {
+                let result = weather::hourly_forecast::<FORECAST_HOURS, _, _>(
+                    &mut client,
+                    &WEATHER_PROVIDERS,
+                    config.coordinates,
+                    &clock.now(),
+                    &units,
+                )
+                .await;
+
+                record(
+                    Source::HourlyWeather,
+                    result,
+                    &mut hourly_forecast,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            if scheduler.is_due(Source::Alerts, now) {
+                let result = alerts::most_severe(
+                    &mut client,
+                    config.coordinates,
+                    &clock.now(),
+                    alert_min_severity,
+                    locale,
+                )
+                .await;
+
+                record(
+                    Source::Alerts,
+                    result,
+                    &mut alert,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            if scheduler.is_due(Source::DailyWeather, now) {
+                let result = weather::daily_forecast::<FORECAST_DAYS, _, _>(
+                    &mut client,
+                    &WEATHER_PROVIDERS,
+                    config.coordinates,
+                    &clock.now(),
+                    &units,
+                )
+                .await;
+
+                record(
+                    Source::DailyWeather,
+                    result,
+                    &mut daily_forecast,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            if scheduler.is_due(Source::AirQuality, now) {
+                let result = air_quality::today(&mut client, config.coordinates).await;
+
+                record(
+                    Source::AirQuality,
+                    result,
+                    &mut air_quality,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            if scheduler.is_due(Source::Todos, now)
+                && let Some((url, auth_header)) = config.todo()
+            {
+                let result =
+                    todo::get_todos::<{ ui::MAX_TODOS }, _, _>(&mut client, url, auth_header).await;
+
+                record(
+                    Source::Todos,
+                    result,
+                    &mut todos,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            if scheduler.is_due(Source::Events, now) {
+                let mut fetched: [ics::Event; MAX_EVENTS] = Default::default();
+
+                let result =
+                    ics::get_events(&mut client, clock.clone(), &config.ical_url, &mut fetched)
+                        .await
+                        .map(|fetched| {
+                            fetched
+                                .iter_mut()
+                                .map(core::mem::take)
+                                .collect::<heapless::Vec<_, MAX_EVENTS>>()
+                        });
+
+                record(
+                    Source::Events,
+                    result,
+                    &mut events,
+                    &mut store.borrow_mut(),
+                    &mut scheduler,
+                    &mut refresh.borrow_mut(),
+                    &clock,
+                );
+            }
+
+            // Locked until the frame is shown so that screenshots never show a partial one.
+            let mut display = display.lock().await;
+
+            // Redraw everything from the cached data of all sources.
             display.clear(Color::Black);
 
-            match weather::hourly_forecast(&mut client).await {
-                Ok(forecast) => {
-                    let hour = clock.now().time().hour();
-                    let forecast = forecast.into_iter().skip(hour as usize).step_by(2).take(3);
+            let now = clock.now();
+
+            if let Some(forecast) = &hourly_forecast {
+                let forecast = forecast
+                    .data
+                    .iter()
+                    .skip_while(|forecast| {
+                        (forecast.time.date(), forecast.time.hour()) < (now.date(), now.hour())
+                    })
+                    .step_by(2)
+                    .take(3);
+
+                ui::draw_hourly_weather(
+                    &mut display.cropped(&layout.hourly_weather),
+                    forecast,
+                    now.date(),
+                    &units,
+                    locale,
+                );
+            }
 
-                    ui::draw_hourly_weather(&mut display, forecast);
-                }
-                Err(err) => {
-                    log::error!("failed to fetch hourly forecast: {err:?}");
-                }
+            // A cached alert may have expired since it was fetched.
+            if let Some(Cached {
+                data: Some(alert), ..
+            }) = &alert
+                && alert.is_active(&now)
+            {
+                ui::draw_alert(&mut display.cropped(&layout.daily_weather), alert, locale);
+            } else if let Some(forecast) = &daily_forecast {
+                ui::draw_daily_weather(
+                    &mut display.cropped(&layout.daily_weather),
+                    forecast.data.iter().skip(1),
+                    config.apparent_temperature,
+                    &units,
+                    locale,
+                );
             }
 
-            match weather::daily_forecast(&mut client).await {
-                Ok(forecast) => {
-                    let forecast = forecast.into_iter().skip(1);
-                    ui::draw_daily_weather(&mut display, forecast);
-                }
-                Err(err) => {
-                    log::error!("failed to fetch daily forecast: {err:?}");
-                }
+            if let Some(air_quality) = &air_quality {
+                ui::draw_air_quality(
+                    &mut display.cropped(&layout.air_quality),
+                    &air_quality.data,
+                    locale,
+                );
             }
 
-            ui::draw_date(&mut display, clock.now().date());
+            ui::draw_date(&mut display.cropped(&layout.date), now.date());
 
-            let mut events: [ics::Event; 10] = Default::default();
+            let todo_list: &[todo::Todo] = todos
+                .as_ref()
+                .map(|todos| todos.data.as_slice())
+                .unwrap_or_default();
 
-            match ics::get_events(&mut client, clock.clone(), ICAL_URL, &mut events).await {
-                Ok(events) => {
-                    ui::draw_events(&mut display, events, clock.now().date());
-                }
-                Err(err) => {
-                    log::error!("failed to fetch events: {err:?}");
-                }
+            if let Some(events) = &events {
+                // The agenda uses the space of missing todos.
+                let agenda = layout.agenda_above_todos(ui::todo_list_height(todo_list.len()));
+
+                ui::draw_events(
+                    &mut display.cropped(&agenda),
+                    &events.data,
+                    now.date(),
+                    locale,
+                );
             }
 
-            if let Some((url, auth_header)) = TODO_URL.zip(TODO_AUTHORIZATION_HEADER) {
-                let mut read_buffer = [0u8; 1024];
+            ui::draw_todos(
+                &mut display.cropped(&layout.todos),
+                todo_list.iter().map(|todo| todo.as_str()),
+            );
 
-                match todo::get_todos(&mut client, url, auth_header, &mut read_buffer).await {
-                    Ok(todos) => {
-                        ui::draw_todos(&mut display, todos);
-                    }
-                    Err(err) => {
-                        log::error!("failed to fetch todos: {err:?}");
-                    }
+            // The newest successful fetch, possibly from before the last restart.
+            let last_update = [
+                fetch_time(&hourly_forecast),
+                fetch_time(&alert),
+                fetch_time(&daily_forecast),
+                fetch_time(&air_quality),
+                fetch_time(&events),
+                fetch_time(&todos),
+            ]
+            .into_iter()
+            .flatten()
+            .max();
+
+            // Without any cached data, rather than an empty dashboard that looks like a quiet day,
+            // tell what is wrong. Otherwise the last data stays, e.g. during a Wi-Fi outage.
+            if refresh.borrow().all_failed() && last_update.is_none() {
+                display.clear(Color::Black);
+
+                if let Some(error) = refresh.borrow().last_error() {
+                    ui::draw_error(&mut *display, error, last_update, locale);
+                }
+            } else {
+                // Failed sources keep showing their last data, marked with when it was fetched.
+                for source in refresh.borrow().failed_sources() {
+                    let (area, fetched) = match source {
+                        Source::HourlyWeather => {
+                            (layout.hourly_weather, fetch_time(&hourly_forecast))
+                        }
+                        Source::Alerts => (layout.daily_weather, fetch_time(&alert)),
+                        Source::DailyWeather => (layout.daily_weather, fetch_time(&daily_forecast)),
+                        Source::AirQuality => (layout.air_quality, fetch_time(&air_quality)),
+                        Source::Events => (layout.agenda, fetch_time(&events)),
+                        Source::Todos => (layout.todos, fetch_time(&todos)),
+                    };
+
+                    ui::draw_stale_marker(&mut display.cropped(&area), fetched, now.date(), locale);
                 }
             }
 
-            epd.wake_up(&mut spi, &mut Delay)
-                .await
-                .expect("waking up the display");
+            let next_fetch = scheduler
+                .next_due()
+                .unwrap_or(Instant::now() + Duration::from_secs(60 * 60));
+            let until_next_fetch = next_fetch.saturating_duration_since(Instant::now());
+            let next_update =
+                &now + jiff::SignedDuration::from_secs(until_next_fetch.as_secs() as i64);
+
+            let content_unchanged = frames.content_unchanged(display.buffer(), now.date());
+
+            // Also drawn when the display is not refreshed, so that screenshots are complete.
+            ui::draw_status_bar(
+                &mut display.cropped(&layout.status_bar),
+                &now,
+                &next_update,
+                signal_strength.rssi(),
+                clock.since_sync(),
+                &refresh.borrow(),
+                locale,
+            );
 
-            epd.update_and_display_frame(&mut spi, display.buffer(), &mut Delay)
-                .await
-                .expect("failed to display frame");
+            if content_unchanged {
+                log::info!("content unchanged, skipping display refresh");
+            } else {
+                let update = frames.update(display.buffer(), &layout.areas(), now.date());
+
+                let shown: Result<(), Error> = async {
+                    epd.wake_up(&mut spi, &mut Delay)
+                        .await
+                        .map_err(|_| Error::Epd("failed to wake up the display"))?;
+
+                    match &update {
+                        frame::Update::Full => {
+                            epd.update_and_display_frame(&mut spi, display.buffer(), &mut Delay)
+                                .await
+                                .map_err(|_| Error::Epd("failed to display frame"))?;
+
+                            // After DisplayRefresh the display needs time to start the
+                            // refresh and assert BUSY. Without this delay wait_until_idle
+                            // can see BUSY still de-asserted and return immediately.
+                            Timer::after(Duration::from_secs(1)).await;
+
+                            epd.wait_until_idle(&mut spi, &mut Delay)
+                                .await
+                                .map_err(|_| {
+                                    Error::Epd("failed to wait until the display is idle")
+                                })?;
+                        }
+                        frame::Update::Partial(regions) => {
+                            log::debug!("refreshing {} regions", regions.len());
+
+                            for region in regions {
+                                // Refreshes the region and waits until the display is idle.
+                                epd.update_partial_frame(
+                                    &mut spi,
+                                    &mut Delay,
+                                    &frame::region_buffer(display.buffer(), region),
+                                    region.top_left.x as u32,
+                                    region.top_left.y as u32,
+                                    region.size.width,
+                                    region.size.height,
+                                )
+                                .await
+                                .map_err(|_| Error::Epd("failed to display partial frame"))?;
+                            }
+                        }
+                    }
 
-            // After DisplayRefresh the display needs time to start the
-            // refresh and assert BUSY. Without this delay wait_until_idle
-            // can see BUSY still de-asserted and return immediately.
-            Timer::after(Duration::from_secs(1)).await;
+                    epd.sleep(&mut spi, &mut Delay)
+                        .await
+                        .map_err(|_| Error::Epd("failed to put EPD to sleep"))?;
 
-            epd.wait_until_idle(&mut spi, &mut Delay)
-                .await
-                .expect("wait until idle to succeed");
+                    Ok(())
+                }
+                .await;
 
-            epd.sleep(&mut spi, &mut Delay)
-                .await
-                .expect("failed to put EPD to sleep");
+                // Keep running, the next refresh may succeed.
+                if let Err(err) = shown {
+                    log::error!("failed to update the display: {err:?}");
+                    frames.invalidate();
+                }
+            }
+
+            drop(display);
 
-            // Schedule next update for the next full hour. Add a minute for some leeway.
-            Timer::after(Duration::from_secs(60 * (61 - clock.now().minute() as u64))).await;
+            if let Either::Second(()) =
+                select(Timer::at(next_fetch), refresh_requested.wait()).await
+            {
+                scheduler.fetch_all(Instant::now());
+            }
         }
     };
 
+    let wifi = async {
+        let controller = if configured {
+            wifi::keep_connection(wifi_controller, &config.networks, signal_strength.clone()).await
+        } else {
+            wifi_controller
+        };
+
+        portal::run(controller, access_point_stack, &config, &store).await
+    };
+
+    let settings_server = server::run(
+        net_stack,
+        server::State {
+            config: &config,
+            store: &store,
+            refresh: &refresh,
+            display: &display,
+            clock: clock.clone(),
+            signal_strength: signal_strength.clone(),
+            refresh_requested: &refresh_requested,
+        },
+    );
+
     join::join(
-        net_runner.run(),
-        join::join3(
-            wifi::keep_connection(wifi_controller, WIFI_SSID, WIFI_PASSWORD),
-            main_logic,
-            sync_time,
-        ),
+        join::join(net_runner.run(), access_point_runner.run()),
+        join::join5(wifi, main_logic, sync_time, mdns_responder, settings_server),
     )
     .await;
 
@@ -226,3 +608,139 @@ async fn main(_spawner: Spawner) -> ! {
         Timer::after(Duration::from_secs(5)).await;
     }
 }
+
+/// Shows `error` in place of the dashboard for errors during startup that leave nothing else to
+/// show and restarts after a while, as they may be transient.
+async fn show_boot_error(
+    epd: &mut Epd,
+    spi: &mut EpdSpi,
+    display: &mut Display7in5,
+    error: Error,
+    locale: Locale,
+) -> ! {
+    log::error!("failed to start: {error:?}");
+
+    display.clear(Color::Black);
+    ui::draw_error(display, &error, None, locale);
+
+    let shown: Result<(), Error> = async {
+        epd.update_and_display_frame(spi, display.buffer(), &mut Delay)
+            .await
+            .map_err(|_| Error::Epd("failed to display frame"))?;
+
+        // See the full refresh in `main`.
+        Timer::after(Duration::from_secs(1)).await;
+
+        epd.wait_until_idle(spi, &mut Delay)
+            .await
+            .map_err(|_| Error::Epd("failed to wait until the display is idle"))?;
+
+        epd.sleep(spi, &mut Delay)
+            .await
+            .map_err(|_| Error::Epd("failed to put EPD to sleep"))
+    }
+    .await;
+
+    if let Err(err) = shown {
+        log::error!("failed to show the error: {err:?}");
+    }
+
+    Timer::after(BOOT_ERROR_RESTART_DELAY).await;
+
+    esp_hal::system::software_reset()
+}
+
+/// Interval in which `source` is fetched.
+fn fetch_interval(source: Source) -> Duration {
+    match source {
+        Source::HourlyWeather | Source::Events => Duration::from_secs(60 * 60),
+        Source::Alerts => Duration::from_secs(15 * 60),
+        Source::DailyWeather | Source::AirQuality => Duration::from_secs(6 * 60 * 60),
+        Source::Todos => Duration::from_secs(5 * 60),
+    }
+}
+
+/// Opens the store in the NVS partition of `flash`.
+fn open_store<'a, 'd>(
+    flash: &'a mut FlashStorage<'d>,
+    partition_table: &'a mut [u8; partitions::PARTITION_TABLE_MAX_LEN],
+) -> Result<Store<FlashRegion<'a, FlashStorage<'d>>>, Error> {
+    let partitions = partitions::read_partition_table(flash, partition_table)
+        .map_err(|_| Error::Storage("failed to read partition table"))?;
+
+    let nvs = partitions
+        .find_partition(PartitionType::Data(DataPartitionSubType::Nvs))
+        .map_err(|_| Error::Storage("failed to read partition table"))?
+        .ok_or(Error::Storage("no NVS partition"))?;
+
+    Store::open(nvs.as_embedded_storage(flash))
+}
+
+/// Loads the stored configuration or, on the first boot, the one set at build time and stores it.
+fn load_config<F: NorFlash>(store: &mut Option<Store<F>>) -> Result<Config, Error> {
+    if let Some(store) = store {
+        match Config::load(store) {
+            Ok(Some(config)) => return Ok(config),
+            Ok(None) => log::info!("no stored configuration, using the one set at build time"),
+            Err(err) => log::error!("failed to load configuration: {err:?}"),
+        }
+    }
+
+    let config = Config::from_env()?;
+
+    if let Some(store) = store
+        && let Err(err) = config.save(store)
+    {
+        log::warn!("failed to store configuration: {err:?}");
+    }
+
+    Ok(config)
+}
+
+/// Loads the stored data of `source`, if any.
+fn load<T: DeserializeOwned, F: NorFlash>(
+    store: &mut Option<Store<F>>,
+    source: Source,
+) -> Option<Cached<T>> {
+    Cached::load(store.as_mut()?, source)
+        .inspect_err(|err| log::warn!("failed to load {source:?}: {err:?}"))
+        .ok()
+        .flatten()
+}
+
+/// Keeps the data of a successful fetch of `source` in `cache` and `store` and schedules the next
+/// fetch.
+fn record<T: Serialize, F: NorFlash>(
+    source: Source,
+    result: Result<T, Error>,
+    cache: &mut Option<Cached<T>>,
+    store: &mut Option<Store<F>>,
+    scheduler: &mut Scheduler,
+    refresh: &mut Refresh,
+    clock: &clock::Clock,
+) {
+    match result {
+        Ok(data) => {
+            let cached = cache.insert(Cached::new(data, clock.now()));
+
+            if let Some(store) = store
+                && let Err(err) = cached.save(store, source)
+            {
+                log::warn!("failed to store {source:?}: {err:?}");
+            }
+
+            scheduler.succeeded(source, Instant::now());
+            refresh.succeeded(source, cached.fetched.clone());
+        }
+        Err(err) => {
+            log::error!("failed to fetch {source:?}: {err:?}");
+            scheduler.failed(source, Instant::now());
+            refresh.failed(source, err);
+        }
+    }
+}
+
+/// Returns when the data in `cache` was fetched.
+fn fetch_time<T>(cache: &Option<Cached<T>>) -> Option<&jiff::Zoned> {
+    cache.as_ref().map(|cache| &cache.fetched)
+}
@@ -0,0 +1,105 @@
+//! Data of the last successful fetch of each source, shown again while fetching it fails and kept
+//! in flash to be shown right after a restart.
+
+use embedded_storage::nor_flash::NorFlash;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+use crate::status::Source;
+use crate::storage::Store;
+
+/// Size of the largest serialized data kept in flash.
+const MAX_STORED_SIZE: usize = 2048;
+
+/// Fetched data together with the time it was fetched.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cached<T> {
+    pub data: T,
+    #[serde(with = "fixed_offset")]
+    pub fetched: jiff::Zoned,
+}
+
+impl<T> Cached<T> {
+    pub fn new(data: T, fetched: jiff::Zoned) -> Self {
+        Self { data, fetched }
+    }
+}
+
+impl<T: Serialize> Cached<T> {
+    /// Stores the cached data of `source` in `store`.
+    pub fn save<F: NorFlash>(&self, store: &mut Store<F>, source: Source) -> Result<(), Error> {
+        let mut buffer = [0; MAX_STORED_SIZE];
+
+        let bytes = postcard::to_slice(self, &mut buffer)
+            .map_err(|_| Error::Storage("failed to serialize cached data"))?;
+
+        store.write(source as u8, bytes)
+    }
+}
+
+impl<T: DeserializeOwned> Cached<T> {
+    /// Loads the cached data of `source` from `store`, if any.
+    pub fn load<F: NorFlash>(store: &mut Store<F>, source: Source) -> Result<Option<Self>, Error> {
+        let mut buffer = [0; MAX_STORED_SIZE];
+
+        store
+            .read(source as u8, &mut buffer)?
+            .map(|bytes| {
+                postcard::from_bytes(bytes)
+                    .map_err(|_| Error::Storage("failed to deserialize cached data"))
+            })
+            .transpose()
+    }
+}
+
+/// Serializes a [`jiff::Zoned`] as timestamp and offset, deserialized with a fixed offset time
+/// zone, so that no time zone database is needed. The local time stays the same, only time zone
+/// transitions after it are lost.
+pub(crate) mod fixed_offset {
+    use jiff::tz::{Offset, TimeZone};
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(zoned: &jiff::Zoned, serializer: S) -> Result<S::Ok, S::Error> {
+        (zoned.timestamp().as_second(), zoned.offset().seconds()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<jiff::Zoned, D::Error> {
+        let (second, offset) = <(i64, i32)>::deserialize(deserializer)?;
+
+        to_zoned(second, offset).map_err(D::Error::custom)
+    }
+
+    fn to_zoned(second: i64, offset: i32) -> Result<jiff::Zoned, jiff::Error> {
+        let offset = Offset::from_seconds(offset)?;
+
+        Ok(jiff::Timestamp::from_second(second)?.to_zoned(TimeZone::fixed(offset)))
+    }
+
+    /// The same for an optional [`jiff::Zoned`].
+    pub mod option {
+        use serde::de::Error as _;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            zoned: &Option<jiff::Zoned>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            zoned
+                .as_ref()
+                .map(|zoned| (zoned.timestamp().as_second(), zoned.offset().seconds()))
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<jiff::Zoned>, D::Error> {
+            <Option<(i64, i32)>>::deserialize(deserializer)?
+                .map(|(second, offset)| super::to_zoned(second, offset).map_err(D::Error::custom))
+                .transpose()
+        }
+    }
+}
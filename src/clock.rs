@@ -3,12 +3,13 @@ extern crate alloc;
 use core::cell::RefCell;
 
 use alloc::rc::Rc;
-use embassy_time::Instant;
+use embassy_time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct Clock {
     timezone: jiff::tz::TimeZone,
     offset: Rc<RefCell<u64>>,
+    last_sync: Rc<RefCell<Option<Instant>>>,
 }
 
 impl Clock {
@@ -16,6 +17,7 @@ impl Clock {
         Self {
             timezone,
             offset: Rc::new(RefCell::new(0)),
+            last_sync: Rc::new(RefCell::new(None)),
         }
     }
 
@@ -24,6 +26,12 @@ impl Clock {
         let now = Instant::now().as_secs();
         let offset = ntp_sec.saturating_sub(now);
         *self.offset.borrow_mut() = offset;
+        *self.last_sync.borrow_mut() = Some(Instant::now());
+    }
+
+    /// Return the time elapsed since the last sync or `None` if the clock was never synced.
+    pub fn since_sync(&self) -> Option<Duration> {
+        self.last_sync.borrow().map(|last_sync| last_sync.elapsed())
     }
 
     /// Return [`jiff::Zoned`] for the current date and time.
@@ -0,0 +1,360 @@
+//! Settings of the dashboard, kept in flash and only taken from the environment at build time
+//! for the first boot.
+
+use embedded_storage::nor_flash::NorFlash;
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::Severity;
+use crate::errors::Error;
+use crate::layout::Layout;
+use crate::locale::Locale;
+use crate::storage::Store;
+use crate::units::Units;
+use crate::weather::Coordinates;
+
+pub mod form;
+
+/// Storage key of the configuration, keys below are used for the cached data of each source.
+const CONFIG_KEY: u8 = 16;
+
+/// Size of the largest serialized configuration.
+const MAX_STORED_SIZE: usize = 2048;
+
+/// Maximum number of known Wi-Fi networks.
+pub const MAX_NETWORKS: usize = 4;
+
+const MAX_SSID_LENGTH: usize = 32;
+const MAX_PASSWORD_LENGTH: usize = 64;
+const MAX_URL_LENGTH: usize = 256;
+const MAX_HOST_NAME_LENGTH: usize = 64;
+/// Maximum length of a single label of a DNS name.
+const MAX_LABEL_LENGTH: usize = 63;
+const MAX_LAYOUT_LENGTH: usize = 256;
+
+/// Minimum length of a WPA2 passphrase.
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+const DEFAULT_NTP_HOST_NAME: &str = "de.pool.ntp.org";
+const DEFAULT_HOST_NAME: &str = "dashboard";
+
+/// Wi-Fi network the dashboard may join.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Network {
+    pub ssid: String<MAX_SSID_LENGTH>,
+    /// Empty for open networks.
+    pub password: String<MAX_PASSWORD_LENGTH>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// Known Wi-Fi networks, the strongest one in range is joined.
+    pub networks: heapless::Vec<Network, MAX_NETWORKS>,
+    pub ical_url: String<MAX_URL_LENGTH>,
+    /// URL and `Authorization` header value of the todo list, if any.
+    pub todo: Option<(String<MAX_URL_LENGTH>, String<MAX_URL_LENGTH>)>,
+    pub ntp_host_name: String<MAX_HOST_NAME_LENGTH>,
+    pub coordinates: Coordinates,
+    /// Name of the dashboard in the local network, announced via mDNS as `<host_name>.local`.
+    pub host_name: String<MAX_LABEL_LENGTH>,
+    /// Language and date format of the display.
+    pub locale: Locale,
+    /// Units in which weather data is requested and displayed.
+    pub units: Units,
+    /// Whether the daily forecast shows "feels like" instead of actual temperatures.
+    pub apparent_temperature: bool,
+    /// Least severe weather warning shown.
+    pub alert_min_severity: Severity,
+    /// Widget areas overriding the default [`Layout`], e.g. `todos=0,104,480,94`, or empty.
+    pub layout: String<MAX_LAYOUT_LENGTH>,
+    /// Password protecting the settings page and refresh of the HTTP server, empty for none.
+    pub admin_password: String<MAX_PASSWORD_LENGTH>,
+}
+
+/// Empty settings, offered for editing in the setup portal when there are no valid ones.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            networks: heapless::Vec::new(),
+            ical_url: String::new(),
+            todo: None,
+            ntp_host_name: DEFAULT_NTP_HOST_NAME.try_into().unwrap_or_default(),
+            coordinates: Coordinates {
+                latitude: 0.0,
+                longitude: 0.0,
+            },
+            host_name: DEFAULT_HOST_NAME.try_into().unwrap_or_default(),
+            locale: Locale::default(),
+            units: Units::default(),
+            apparent_temperature: false,
+            alert_min_severity: Severity::Moderate,
+            layout: String::new(),
+            admin_password: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the `WIFI_SSID`, `WIFI_PASSWORD`, `ICAL_URL`, `TODO_URL`,
+    /// `TODO_AUTHORIZATION_HEADER`, `NTP_HOST_NAME`, `WEATHER_LAT`, `WEATHER_LON`, `HOST_NAME`,
+    /// `LOCALE`, `UNITS`, `APPARENT_TEMPERATURE`, `ALERT_MIN_SEVERITY`, `LAYOUT` and
+    /// `ADMIN_PASSWORD` environment variables set at build time. `WIFI_SSID` is the only known
+    /// network, more can be added in the settings page.
+    pub fn from_env() -> Result<Self, Error> {
+        let todo = match (
+            option_env!("TODO_URL"),
+            option_env!("TODO_AUTHORIZATION_HEADER"),
+        ) {
+            (Some(url), Some(authorization_header)) => Some((
+                string(url, "TODO_URL too long")?,
+                string(authorization_header, "TODO_AUTHORIZATION_HEADER too long")?,
+            )),
+            _ => None,
+        };
+
+        let mut networks = heapless::Vec::new();
+
+        if let Some(ssid) = option_env!("WIFI_SSID") {
+            let network = Network {
+                ssid: string(ssid, "WIFI_SSID too long")?,
+                password: string(
+                    option_env!("WIFI_PASSWORD").unwrap_or_default(),
+                    "WIFI_PASSWORD too long",
+                )?,
+            };
+
+            networks
+                .push(network)
+                .map_err(|_| Error::Config("too many Wi-Fi networks"))?;
+        }
+
+        let config = Self {
+            networks,
+            ical_url: string(
+                option_env!("ICAL_URL").unwrap_or_default(),
+                "ICAL_URL too long",
+            )?,
+            todo,
+            ntp_host_name: string(
+                option_env!("NTP_HOST_NAME").unwrap_or(DEFAULT_NTP_HOST_NAME),
+                "NTP_HOST_NAME too long",
+            )?,
+            coordinates: Coordinates {
+                latitude: coordinate(option_env!("WEATHER_LAT"), "invalid WEATHER_LAT")?,
+                longitude: coordinate(option_env!("WEATHER_LON"), "invalid WEATHER_LON")?,
+            },
+            host_name: string(
+                option_env!("HOST_NAME").unwrap_or(DEFAULT_HOST_NAME),
+                "HOST_NAME too long",
+            )?,
+            locale: option_env!("LOCALE")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            units: option_env!("UNITS")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            apparent_temperature: option_env!("APPARENT_TEMPERATURE")
+                .map(|value| flag(value, "APPARENT_TEMPERATURE must be true or false"))
+                .transpose()?
+                .unwrap_or_default(),
+            alert_min_severity: option_env!("ALERT_MIN_SEVERITY")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or(Severity::Moderate),
+            layout: string(option_env!("LAYOUT").unwrap_or_default(), "LAYOUT too long")?,
+            admin_password: string(
+                option_env!("ADMIN_PASSWORD").unwrap_or_default(),
+                "ADMIN_PASSWORD too long",
+            )?,
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks that all settings are usable.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.networks.is_empty() {
+            return Err(Error::Config("Wi-Fi network missing"));
+        }
+
+        for (index, network) in self.networks.iter().enumerate() {
+            if network.ssid.is_empty() {
+                return Err(Error::Config("Wi-Fi SSID missing"));
+            }
+
+            if !network.password.is_empty() && network.password.len() < MIN_PASSWORD_LENGTH {
+                return Err(Error::Config("Wi-Fi password too short"));
+            }
+
+            if self.networks[..index]
+                .iter()
+                .any(|other| other.ssid == network.ssid)
+            {
+                return Err(Error::Config("Wi-Fi network listed twice"));
+            }
+        }
+
+        if !is_http_url(&self.ical_url) {
+            return Err(Error::Config(
+                "iCal URL must start with http:// or https://",
+            ));
+        }
+
+        if let Some((url, _)) = &self.todo
+            && !is_http_url(url)
+        {
+            return Err(Error::Config(
+                "todo URL must start with http:// or https://",
+            ));
+        }
+
+        if self.ntp_host_name.is_empty() {
+            return Err(Error::Config("NTP host name missing"));
+        }
+
+        if !self.coordinates.is_valid() {
+            return Err(Error::Config("coordinates out of range"));
+        }
+
+        if !is_label(&self.host_name) {
+            return Err(Error::Config(
+                "host name must only contain letters, digits and inner hyphens",
+            ));
+        }
+
+        self.layout.parse::<Layout>()?;
+
+        Ok(())
+    }
+
+    /// Returns the todo list URL and `Authorization` header value, if configured.
+    pub fn todo(&self) -> Option<(&str, &str)> {
+        self.todo
+            .as_ref()
+            .map(|(url, authorization_header)| (url.as_str(), authorization_header.as_str()))
+    }
+
+    /// Returns the layout of the widgets.
+    pub fn layout(&self) -> Layout {
+        // Only valid layouts pass validation.
+        self.layout.parse().unwrap_or_default()
+    }
+
+    /// Loads the stored configuration, if any and valid.
+    pub fn load<F: NorFlash>(store: &mut Store<F>) -> Result<Option<Self>, Error> {
+        let mut buffer = [0; MAX_STORED_SIZE];
+
+        let Some(bytes) = store.read(CONFIG_KEY, &mut buffer)? else {
+            return Ok(None);
+        };
+
+        let config: Self = postcard::from_bytes(bytes)
+            .map_err(|_| Error::Storage("failed to deserialize configuration"))?;
+
+        config.validate()?;
+
+        Ok(Some(config))
+    }
+
+    /// Stores the configuration to be loaded on the next boot.
+    pub fn save<F: NorFlash>(&self, store: &mut Store<F>) -> Result<(), Error> {
+        let mut buffer = [0; MAX_STORED_SIZE];
+
+        let bytes = postcard::to_slice(self, &mut buffer)
+            .map_err(|_| Error::Storage("failed to serialize configuration"))?;
+
+        store.write(CONFIG_KEY, bytes)
+    }
+}
+
+fn string<const N: usize>(value: &str, error: &'static str) -> Result<String<N>, Error> {
+    value.try_into().map_err(|_| Error::Config(error))
+}
+
+fn flag(value: &str, error: &'static str) -> Result<bool, Error> {
+    value.parse().map_err(|_| Error::Config(error))
+}
+
+fn coordinate(value: Option<&str>, error: &'static str) -> Result<f32, Error> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or(Error::Config(error))
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn is_label(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            networks: heapless::Vec::from_slice(&[Network {
+                ssid: "Home".try_into().unwrap(),
+                password: "secret123".try_into().unwrap(),
+            }])
+            .unwrap(),
+            ical_url: "http://example.org/calendar.ics".try_into().unwrap(),
+            ..Config::default()
+        }
+    }
+
+    fn error(config: &Config) -> &'static str {
+        match config.validate() {
+            Err(Error::Config(message)) => message,
+            result => panic!("expected configuration error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_settings() {
+        config().validate().unwrap();
+
+        let mut open = config();
+        open.networks[0].password.clear();
+        open.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_networks() {
+        let mut config = config();
+
+        config.networks[0].password = "short".try_into().unwrap();
+        assert_eq!(error(&config), "Wi-Fi password too short");
+
+        config.networks[0].ssid.clear();
+        assert_eq!(error(&config), "Wi-Fi SSID missing");
+
+        config.networks.clear();
+        assert_eq!(error(&config), "Wi-Fi network missing");
+    }
+
+    #[test]
+    fn rejects_invalid_host_names() {
+        for host_name in ["", "-kitchen", "kitchen-", "kitchen.local", "k\u{fc}che"] {
+            let config = Config {
+                host_name: host_name.try_into().unwrap(),
+                ..config()
+            };
+
+            assert_eq!(
+                error(&config),
+                "host name must only contain letters, digits and inner hyphens",
+                "{host_name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,746 @@
+//! HTML form to edit the [`Config`], served by the setup portal and the settings server.
+//!
+//! Secrets are never sent back to the browser: empty password fields keep the stored values.
+
+use core::fmt::{self, Write};
+
+use heapless::String;
+
+use super::{
+    Config, MAX_HOST_NAME_LENGTH, MAX_LABEL_LENGTH, MAX_LAYOUT_LENGTH, MAX_NETWORKS,
+    MAX_PASSWORD_LENGTH, MAX_SSID_LENGTH, MAX_URL_LENGTH, Network,
+};
+use crate::alerts::Severity;
+use crate::errors::Error;
+use crate::http::{self, Escaped};
+use crate::locale::Locale;
+use crate::units::Units;
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:32em;margin:auto;padding:1em}\
+    label{display:block;margin-top:1em}input{display:block;width:100%;box-sizing:border-box}\
+    input[type=checkbox]{display:inline;width:auto}button{margin-top:1.5em}.error{color:#b00}";
+
+/// Writes the settings page showing `config`, offering the `networks` in range for the SSIDs and
+/// `error` of a previous submission, if any. The form posts to `action`.
+pub fn render<'a>(
+    page: &mut impl Write,
+    action: &str,
+    config: &Config,
+    networks: impl Iterator<Item = &'a str>,
+    error: Option<&Error>,
+) -> fmt::Result {
+    write!(
+        page,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+        <title>Dashboard settings</title><style>{STYLE}</style></head>\
+        <body><h1>Dashboard settings</h1>"
+    )?;
+
+    if let Some(error) = error {
+        let message: String<96> = heapless::format!("{error:?}").unwrap_or_default();
+        write!(page, "<p class=\"error\">{}</p>", Escaped(&message))?;
+    }
+
+    write!(page, "<form method=\"post\" action=\"{action}\">")?;
+
+    write!(
+        page,
+        "<p>The strongest of the Wi-Fi networks in range is joined, clear a name to forget it.</p>"
+    )?;
+
+    for index in 0..MAX_NETWORKS {
+        let network = config.networks.get(index);
+
+        write!(
+            page,
+            "<label>Wi-Fi network {}<input name=\"wifi_ssid_{index}\" list=\"networks\" maxlength=\"{MAX_SSID_LENGTH}\" value=\"{}\"{}></label>\
+            <label>Wi-Fi password {}<input name=\"wifi_password_{index}\" type=\"password\" maxlength=\"{MAX_PASSWORD_LENGTH}\" placeholder=\"{}\"></label>",
+            index + 1,
+            Escaped(
+                network
+                    .map(|network| network.ssid.as_str())
+                    .unwrap_or_default()
+            ),
+            if index == 0 { " required" } else { "" },
+            index + 1,
+            match network {
+                Some(network) if !network.password.is_empty() => "unchanged",
+                _ => "none",
+            }
+        )?;
+    }
+
+    write!(page, "<datalist id=\"networks\">")?;
+
+    for network in networks {
+        write!(page, "<option value=\"{}\">", Escaped(network))?;
+    }
+
+    write!(page, "</datalist>")?;
+
+    write!(
+        page,
+        "<label>Calendar URL<input name=\"ical_url\" type=\"url\" maxlength=\"{MAX_URL_LENGTH}\" value=\"{}\" required></label>",
+        Escaped(&config.ical_url)
+    )?;
+
+    let (todo_url, todo_authorization_header) = config.todo().unwrap_or_default();
+
+    write!(
+        page,
+        "<label>Todo list URL<input name=\"todo_url\" type=\"url\" maxlength=\"{MAX_URL_LENGTH}\" value=\"{}\" placeholder=\"none\"></label>\
+        <label>Todo list Authorization header<input name=\"todo_authorization_header\" type=\"password\" maxlength=\"{MAX_URL_LENGTH}\" placeholder=\"{}\"></label>",
+        Escaped(todo_url),
+        if todo_authorization_header.is_empty() {
+            ""
+        } else {
+            "unchanged"
+        }
+    )?;
+
+    write!(
+        page,
+        "<label>NTP server<input name=\"ntp_host_name\" maxlength=\"{MAX_HOST_NAME_LENGTH}\" value=\"{}\" required></label>\
+        <label>Latitude<input name=\"latitude\" type=\"number\" step=\"any\" min=\"-90\" max=\"90\" value=\"{}\" required></label>\
+        <label>Longitude<input name=\"longitude\" type=\"number\" step=\"any\" min=\"-180\" max=\"180\" value=\"{}\" required></label>",
+        Escaped(&config.ntp_host_name),
+        config.coordinates.latitude,
+        config.coordinates.longitude
+    )?;
+
+    write!(
+        page,
+        "<label>Name in the local network<input name=\"host_name\" maxlength=\"{MAX_LABEL_LENGTH}\" pattern=\"[A-Za-z0-9]([A-Za-z0-9\\-]*[A-Za-z0-9])?\" value=\"{}\" required></label>",
+        Escaped(&config.host_name)
+    )?;
+
+    write!(page, "<label>Language<select name=\"locale\">")?;
+
+    for locale in Locale::ALL {
+        write!(
+            page,
+            "<option value=\"{}\"{}>{}</option>",
+            locale.code(),
+            if locale == config.locale {
+                " selected"
+            } else {
+                ""
+            },
+            language(locale)
+        )?;
+    }
+
+    write!(page, "</select></label>")?;
+
+    write!(page, "<label>Units<select name=\"units\">")?;
+
+    for (units, value, name) in [
+        (Units::METRIC, "metric", "Metric (°C, km/h, mm)"),
+        (Units::IMPERIAL, "imperial", "Imperial (°F, mph, in)"),
+    ] {
+        write!(
+            page,
+            "<option value=\"{value}\"{}>{name}</option>",
+            if units == config.units {
+                " selected"
+            } else {
+                ""
+            }
+        )?;
+    }
+
+    write!(page, "</select></label>")?;
+
+    write!(
+        page,
+        "<label>Daily temperatures<select name=\"apparent_temperature\">"
+    )?;
+
+    for (apparent, name) in [(false, "Actual"), (true, "Feels like")] {
+        write!(
+            page,
+            "<option value=\"{apparent}\"{}>{name}</option>",
+            if apparent == config.apparent_temperature {
+                " selected"
+            } else {
+                ""
+            }
+        )?;
+    }
+
+    write!(page, "</select></label>")?;
+
+    write!(
+        page,
+        "<label>Weather warnings from<select name=\"alert_min_severity\">"
+    )?;
+
+    for (severity, value, name) in [
+        (Severity::Minor, "minor", "Minor"),
+        (Severity::Moderate, "moderate", "Moderate"),
+        (Severity::Severe, "severe", "Severe"),
+        (Severity::Extreme, "extreme", "Extreme"),
+    ] {
+        write!(
+            page,
+            "<option value=\"{value}\"{}>{name}</option>",
+            if severity == config.alert_min_severity {
+                " selected"
+            } else {
+                ""
+            }
+        )?;
+    }
+
+    write!(page, "</select></label>")?;
+
+    write!(
+        page,
+        "<label>Widget areas<input name=\"layout\" maxlength=\"{MAX_LAYOUT_LENGTH}\" value=\"{}\" placeholder=\"default, or widget=x,y,width,height;...\"></label>",
+        Escaped(&config.layout)
+    )?;
+
+    write!(
+        page,
+        "<label>Password of this page<input name=\"admin_password\" type=\"password\" maxlength=\"{MAX_PASSWORD_LENGTH}\" placeholder=\"{}\"></label>\
+        <label><input name=\"remove_admin_password\" type=\"checkbox\"> Remove the password</label>",
+        if config.admin_password.is_empty() {
+            "none"
+        } else {
+            "unchanged"
+        }
+    )?;
+
+    write!(
+        page,
+        "<button>Save and restart</button></form></body></html>"
+    )
+}
+
+/// Returns `current` updated with the submitted `form` and validated. Fields missing from the
+/// form keep their current value.
+pub fn parse(current: &Config, form: &str) -> Result<Config, Error> {
+    let mut config = current.clone();
+    let mut ssids: [Option<String<MAX_SSID_LENGTH>>; MAX_NETWORKS] = Default::default();
+    let mut passwords: [Option<String<MAX_PASSWORD_LENGTH>>; MAX_NETWORKS] = Default::default();
+    let mut todo_url = None;
+    let mut todo_authorization_header = None;
+    let mut admin_password = None;
+    let mut remove_admin_password = false;
+
+    for (name, value) in http::form_fields(form) {
+        if let Some(index) = network_index(name, "wifi_ssid_") {
+            ssids[index] = Some(field(value, "invalid Wi-Fi SSID")?);
+            continue;
+        }
+
+        if let Some(index) = network_index(name, "wifi_password_") {
+            passwords[index] = Some(field(value, "invalid Wi-Fi password")?);
+            continue;
+        }
+
+        match name {
+            "ical_url" => config.ical_url = field(value, "invalid iCal URL")?,
+            "todo_url" => todo_url = Some(field(value, "invalid todo URL")?),
+            "todo_authorization_header" => {
+                todo_authorization_header = Some(field(value, "invalid todo Authorization header")?)
+            }
+            "ntp_host_name" => config.ntp_host_name = field(value, "invalid NTP host name")?,
+            "latitude" => config.coordinates.latitude = coordinate(value, "invalid latitude")?,
+            "longitude" => config.coordinates.longitude = coordinate(value, "invalid longitude")?,
+            "host_name" => config.host_name = field(value, "invalid host name")?,
+            "locale" => config.locale = field::<2>(value, "invalid locale")?.parse()?,
+            "units" => config.units = field::<8>(value, "invalid units")?.parse()?,
+            "apparent_temperature" => {
+                config.apparent_temperature = field::<8>(value, "invalid apparent temperature")?
+                    .parse()
+                    .map_err(|_| Error::Config("invalid apparent temperature"))?
+            }
+            "layout" => config.layout = field(value, "invalid layout")?,
+            "admin_password" => admin_password = Some(field(value, "invalid admin password")?),
+            "remove_admin_password" => remove_admin_password = true,
+            "alert_min_severity" => {
+                config.alert_min_severity = field::<8>(value, "invalid alert severity")?.parse()?
+            }
+            _ => {}
+        }
+    }
+
+    if ssids.iter().any(Option::is_some) {
+        config.networks.clear();
+
+        for (ssid, password) in ssids.into_iter().zip(passwords) {
+            let Some(ssid) = ssid.filter(|ssid| !ssid.is_empty()) else {
+                continue;
+            };
+
+            let password = match password {
+                Some(password) if !password.is_empty() => password,
+                // Without a password, the current one is only kept for the same network.
+                _ => current
+                    .networks
+                    .iter()
+                    .find(|network| network.ssid == ssid)
+                    .map(|network| network.password.clone())
+                    .unwrap_or_default(),
+            };
+
+            config
+                .networks
+                .push(Network { ssid, password })
+                .map_err(|_| Error::Config("too many Wi-Fi networks"))?;
+        }
+    }
+
+    if let Some(url) = todo_url {
+        config.todo = if url.is_empty() {
+            None
+        } else {
+            let authorization_header = match todo_authorization_header {
+                Some(header) if !header.is_empty() => header,
+                _ => current
+                    .todo
+                    .as_ref()
+                    .map(|(_, header)| header.clone())
+                    .ok_or(Error::Config("todo Authorization header missing"))?,
+            };
+
+            Some((url, authorization_header))
+        };
+    }
+
+    if remove_admin_password {
+        config.admin_password.clear();
+    } else if let Some(password) = admin_password.filter(|password| !password.is_empty()) {
+        config.admin_password = password;
+    }
+
+    config.validate()?;
+
+    Ok(config)
+}
+
+/// Returns the name of the language of `locale` in that language.
+fn language(locale: Locale) -> &'static str {
+    match locale {
+        Locale::De => "Deutsch",
+        Locale::En => "English",
+        Locale::Fr => "Français",
+        Locale::Nl => "Nederlands",
+        Locale::Es => "Español",
+    }
+}
+
+/// Returns the index of the network of field `name` starting with `prefix`, if valid.
+fn network_index(name: &str, prefix: &str) -> Option<usize> {
+    name.strip_prefix(prefix)?
+        .parse()
+        .ok()
+        .filter(|&index| index < MAX_NETWORKS)
+}
+
+fn field<const N: usize>(value: &str, error: &'static str) -> Result<String<N>, Error> {
+    http::decode(value).map_err(|_| Error::Config(error))
+}
+
+fn coordinate(value: &str, error: &'static str) -> Result<f32, Error> {
+    field::<16>(value, error)?
+        .parse()
+        .map_err(|_| Error::Config(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+
+    fn network(ssid: &str, password: &str) -> Network {
+        Network {
+            ssid: ssid.try_into().unwrap(),
+            password: password.try_into().unwrap(),
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            networks: heapless::Vec::from_slice(&[
+                network("Home", "secret123"),
+                network("Flat", ""),
+            ])
+            .unwrap(),
+            ical_url: "https://example.org/calendar.ics".try_into().unwrap(),
+            todo: Some((
+                "https://example.org/todo".try_into().unwrap(),
+                "Bearer token".try_into().unwrap(),
+            )),
+            ..Config::default()
+        }
+    }
+
+    fn error(result: Result<Config, Error>) -> &'static str {
+        match result {
+            Err(Error::Config(message)) => message,
+            result => panic!("expected configuration error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_complete_form() {
+        let config = parse(
+            &Config::default(),
+            "wifi_ssid_0=Home+Net&wifi_password_0=secret%21123&wifi_ssid_1=&wifi_password_1=\
+            &ical_url=https%3A%2F%2Fexample.org%2Fa.ics&todo_url=&todo_authorization_header=\
+            &ntp_host_name=pool.ntp.org&latitude=52.52&longitude=-13.405&host_name=kitchen",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.networks.as_slice(),
+            [network("Home Net", "secret!123")]
+        );
+        assert_eq!(config.ical_url, "https://example.org/a.ics");
+        assert_eq!(config.todo(), None);
+        assert_eq!(config.ntp_host_name, "pool.ntp.org");
+        assert_eq!(config.coordinates.latitude, 52.52);
+        assert_eq!(config.coordinates.longitude, -13.405);
+        assert_eq!(config.host_name, "kitchen");
+    }
+
+    #[test]
+    fn keeps_fields_missing_from_form() {
+        assert_eq!(parse(&config(), "").unwrap(), config());
+        assert_eq!(parse(&config(), "unknown=1").unwrap(), config());
+    }
+
+    #[test]
+    fn blank_password_keeps_stored_one() {
+        let parsed = parse(
+            &config(),
+            "wifi_ssid_0=Home&wifi_password_0=&wifi_ssid_1=Flat&wifi_password_1=",
+        )
+        .unwrap();
+
+        assert_eq!(parsed.networks, config().networks);
+
+        // Also when the network moved to another slot.
+        let parsed = parse(
+            &config(),
+            "wifi_ssid_0=Office&wifi_password_0=office123&wifi_ssid_1=Home&wifi_password_1=",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.networks.as_slice(),
+            [network("Office", "office123"), network("Home", "secret123")]
+        );
+    }
+
+    #[test]
+    fn blank_password_of_new_network_is_open() {
+        let parsed = parse(&config(), "wifi_ssid_0=Other&wifi_password_0=").unwrap();
+
+        assert_eq!(parsed.networks.as_slice(), [network("Other", "")]);
+    }
+
+    #[test]
+    fn new_password_replaces_stored_one() {
+        let parsed = parse(&config(), "wifi_ssid_0=Home&wifi_password_0=changed123").unwrap();
+
+        assert_eq!(parsed.networks.as_slice(), [network("Home", "changed123")]);
+    }
+
+    #[test]
+    fn removes_networks_with_blank_names() {
+        let parsed = parse(
+            &config(),
+            "wifi_ssid_0=&wifi_password_0=&wifi_ssid_1=Flat&wifi_password_1=",
+        )
+        .unwrap();
+
+        assert_eq!(parsed.networks.as_slice(), [network("Flat", "")]);
+
+        assert_eq!(
+            error(parse(&config(), "wifi_ssid_0=&wifi_ssid_1=")),
+            "Wi-Fi network missing"
+        );
+    }
+
+    #[test]
+    fn ignores_networks_beyond_maximum() {
+        assert_eq!(parse(&config(), "wifi_ssid_4=Other").unwrap(), config());
+        assert_eq!(parse(&config(), "wifi_ssid_x=Other").unwrap(), config());
+    }
+
+    #[test]
+    fn blank_todo_header_keeps_stored_one() {
+        let parsed = parse(
+            &config(),
+            "todo_url=https%3A%2F%2Fexample.org%2Fother&todo_authorization_header=",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.todo(),
+            Some(("https://example.org/other", "Bearer token"))
+        );
+
+        let parsed = parse(&config(), "todo_url=https%3A%2F%2Fexample.org%2Fother").unwrap();
+
+        assert_eq!(
+            parsed.todo(),
+            Some(("https://example.org/other", "Bearer token"))
+        );
+
+        let parsed = parse(
+            &config(),
+            "todo_url=https%3A%2F%2Fexample.org%2Ftodo&todo_authorization_header=Bearer+new",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.todo(),
+            Some(("https://example.org/todo", "Bearer new"))
+        );
+    }
+
+    #[test]
+    fn blank_todo_url_disables_todos() {
+        let parsed = parse(&config(), "todo_url=&todo_authorization_header=").unwrap();
+
+        assert_eq!(parsed.todo(), None);
+    }
+
+    #[test]
+    fn rejects_todo_without_authorization_header() {
+        let config = Config {
+            todo: None,
+            ..config()
+        };
+
+        assert_eq!(
+            error(parse(
+                &config,
+                "todo_url=https%3A%2F%2Fexample.org%2Ftodo&todo_authorization_header="
+            )),
+            "todo Authorization header missing"
+        );
+    }
+
+    #[test]
+    fn parses_locale() {
+        for locale in Locale::ALL {
+            let form = std::format!("locale={}", locale.code());
+
+            assert_eq!(parse(&config(), &form).unwrap().locale, locale);
+        }
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(
+            parse(&config(), "units=imperial").unwrap().units,
+            Units::IMPERIAL
+        );
+        assert_eq!(
+            parse(&config(), "units=metric").unwrap().units,
+            Units::METRIC
+        );
+    }
+
+    #[test]
+    fn parses_apparent_temperature() {
+        assert!(
+            parse(&config(), "apparent_temperature=true")
+                .unwrap()
+                .apparent_temperature
+        );
+
+        let apparent = Config {
+            apparent_temperature: true,
+            ..config()
+        };
+
+        assert!(
+            !parse(&apparent, "apparent_temperature=false")
+                .unwrap()
+                .apparent_temperature
+        );
+    }
+
+    #[test]
+    fn parses_alert_min_severity() {
+        for (value, severity) in [
+            ("minor", Severity::Minor),
+            ("moderate", Severity::Moderate),
+            ("severe", Severity::Severe),
+            ("extreme", Severity::Extreme),
+        ] {
+            let form = std::format!("alert_min_severity={value}");
+
+            assert_eq!(
+                parse(&config(), &form).unwrap().alert_min_severity,
+                severity
+            );
+        }
+    }
+
+    #[test]
+    fn parses_layout() {
+        let parsed = parse(
+            &config(),
+            "layout=todos%3D0%2C104%2C480%2C94%3Bagenda%3D0%2C198%2C480%2C586",
+        )
+        .unwrap();
+
+        assert_eq!(parsed.layout, "todos=0,104,480,94;agenda=0,198,480,586");
+        assert_eq!(parsed.layout().todos.top_left.y, 104);
+        assert_eq!(parsed.layout().agenda.top_left.y, 198);
+
+        let parsed = parse(&parsed, "layout=").unwrap();
+
+        assert_eq!(parsed.layout(), Layout::default());
+    }
+
+    #[test]
+    fn blank_admin_password_keeps_stored_one() {
+        let protected = parse(&config(), "admin_password=secret").unwrap();
+
+        assert_eq!(protected.admin_password, "secret");
+        assert_eq!(
+            parse(&protected, "admin_password=").unwrap().admin_password,
+            "secret"
+        );
+        assert_eq!(
+            parse(&protected, "admin_password=changed")
+                .unwrap()
+                .admin_password,
+            "changed"
+        );
+        assert_eq!(
+            parse(
+                &protected,
+                "admin_password=changed&remove_admin_password=on"
+            )
+            .unwrap()
+            .admin_password,
+            ""
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let long_ssid = "x".repeat(MAX_SSID_LENGTH + 1);
+
+        for (form, message) in [
+            (
+                &*std::format!("wifi_ssid_0={long_ssid}"),
+                "invalid Wi-Fi SSID",
+            ),
+            (
+                "wifi_ssid_0=Home&wifi_password_0=%",
+                "invalid Wi-Fi password",
+            ),
+            ("ical_url=%GG", "invalid iCal URL"),
+            ("todo_url=%", "invalid todo URL"),
+            (
+                "todo_url=https%3A%2F%2Fx&todo_authorization_header=%",
+                "invalid todo Authorization header",
+            ),
+            ("ntp_host_name=%", "invalid NTP host name"),
+            ("latitude=north", "invalid latitude"),
+            ("longitude=", "invalid longitude"),
+            ("host_name=%FF", "invalid host name"),
+            ("locale=deu", "invalid locale"),
+            ("locale=xx", "unknown locale"),
+            ("units=imperial%21", "invalid units"),
+            ("units=kelvin", "unknown units, expected metric or imperial"),
+            ("apparent_temperature=1", "invalid apparent temperature"),
+            ("alert_min_severity=moderately", "invalid alert severity"),
+            ("layout=%", "invalid layout"),
+            ("admin_password=%", "invalid admin password"),
+            (
+                "alert_min_severity=mild",
+                "unknown alert severity, expected minor, moderate, severe or extreme",
+            ),
+        ] {
+            assert_eq!(error(parse(&config(), form)), message, "{form}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        for (form, message) in [
+            (
+                "wifi_ssid_0=Home&wifi_password_0=short",
+                "Wi-Fi password too short",
+            ),
+            (
+                "wifi_ssid_0=Home&wifi_ssid_1=Home",
+                "Wi-Fi network listed twice",
+            ),
+            (
+                "ical_url=example.org",
+                "iCal URL must start with http:// or https://",
+            ),
+            (
+                "todo_url=ftp%3A%2F%2Fexample.org",
+                "todo URL must start with http:// or https://",
+            ),
+            ("ntp_host_name=", "NTP host name missing"),
+            ("latitude=90.5", "coordinates out of range"),
+            ("longitude=-181", "coordinates out of range"),
+            (
+                "host_name=kitchen.local",
+                "host name must only contain letters, digits and inner hyphens",
+            ),
+            ("layout=todos", "expected widget=x,y,width,height"),
+            ("layout=todos%3D0%2C0%2C480", "invalid widget area"),
+            (
+                "layout=todos%3D0%2C0%2C481%2C94",
+                "widget area exceeds display",
+            ),
+            ("layout=clock%3D0%2C0%2C48%2C16", "unknown widget"),
+        ] {
+            assert_eq!(error(parse(&config(), form)), message, "{form}");
+        }
+    }
+
+    #[test]
+    fn renders_settings_without_secrets() {
+        let mut page = std::string::String::new();
+        let config = Config {
+            admin_password: "admin-secret".try_into().unwrap(),
+            ..config()
+        };
+
+        render(
+            &mut page,
+            "/config",
+            &config,
+            ["Home", "Caf\u{e9} <free>"].into_iter(),
+            Some(&Error::Config("bad & worse")),
+        )
+        .unwrap();
+
+        assert!(page.contains("action=\"/config\""));
+        assert!(page.contains("Config(&quot;bad &amp; worse&quot;)"));
+        assert!(page.contains("<option value=\"Caf\u{e9} &lt;free&gt;\">"));
+        assert!(page.contains(
+            "name=\"wifi_ssid_0\" list=\"networks\" maxlength=\"32\" value=\"Home\" required"
+        ));
+        assert!(page.contains("name=\"wifi_ssid_3\""));
+        assert!(page.contains("value=\"https://example.org/todo\""));
+        assert!(page.contains("<option value=\"de\" selected>Deutsch</option>"));
+        assert!(page.contains("<option value=\"es\">Español</option>"));
+        assert!(page.contains("<option value=\"metric\" selected>"));
+        assert!(page.contains("<option value=\"false\" selected>Actual</option>"));
+        assert!(page.contains("<option value=\"moderate\" selected>"));
+        assert!(page.contains(
+            "name=\"admin_password\" type=\"password\" maxlength=\"64\" placeholder=\"unchanged\""
+        ));
+        assert!(!page.contains("secret123"));
+        assert!(!page.contains("admin-secret"));
+        assert!(!page.contains("Bearer"));
+    }
+}
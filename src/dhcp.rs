@@ -0,0 +1,473 @@
+//! DHCP server of the setup portal handing out addresses of a small pool to the clients of the
+//! access point, with the access point itself as router and DNS server.
+
+use core::net::Ipv4Addr;
+
+use embassy_net::Stack;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+
+const SERVER_PORT: u16 = 67;
+const CLIENT_PORT: u16 = 68;
+
+/// Number of addresses handed out, following the address of the server.
+const POOL_SIZE: usize = 8;
+
+const LEASE_SECONDS: u32 = 60 * 60;
+
+/// Offset of the options after the fixed BOOTP fields and the magic cookie.
+const OPTIONS_OFFSET: usize = 240;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+
+/// Replies are padded to the minimum BOOTP message size some clients insist on.
+pub const REPLY_SIZE: usize = 300;
+
+const BOOT_REQUEST: u8 = 1;
+const BOOT_REPLY: u8 = 2;
+
+const OPTION_PAD: u8 = 0;
+const OPTION_SUBNET_MASK: u8 = 1;
+const OPTION_ROUTER: u8 = 3;
+const OPTION_DNS_SERVER: u8 = 6;
+const OPTION_REQUESTED_ADDRESS: u8 = 50;
+const OPTION_LEASE_TIME: u8 = 51;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_END: u8 = 255;
+
+const DISCOVER: u8 = 1;
+const OFFER: u8 = 2;
+const REQUEST: u8 = 3;
+const ACK: u8 = 5;
+const NAK: u8 = 6;
+
+pub struct Server {
+    address: Ipv4Addr,
+    /// Hardware addresses of the clients leasing each address of the pool.
+    leases: [Option<[u8; 6]>; POOL_SIZE],
+    /// Lease given to a new client once the pool is exhausted.
+    next_reused: usize,
+}
+
+impl Server {
+    /// Creates a server at `address` in a /24 network, handing out the addresses following it.
+    pub fn new(address: Ipv4Addr) -> Self {
+        Self {
+            address,
+            leases: [None; POOL_SIZE],
+            next_reused: 0,
+        }
+    }
+
+    /// Handles `request` and writes the reply into `reply`. Returns `false` if there is nothing to
+    /// reply.
+    pub fn reply(&mut self, request: &[u8], reply: &mut [u8; REPLY_SIZE]) -> bool {
+        if request.len() < OPTIONS_OFFSET
+            || request[0] != BOOT_REQUEST
+            || request[236..240] != MAGIC_COOKIE
+        {
+            return false;
+        }
+
+        let options = &request[OPTIONS_OFFSET..];
+        let mut hardware_address = [0; 6];
+        hardware_address.copy_from_slice(&request[28..34]);
+
+        let message_type = match option(options, OPTION_MESSAGE_TYPE) {
+            Some(&[DISCOVER]) => OFFER,
+            Some(&[REQUEST]) => {
+                // The client chose the offer of another server.
+                if let Some(server) = option(options, OPTION_SERVER_ID)
+                    && server != self.address.octets()
+                {
+                    return false;
+                }
+
+                let requested =
+                    option(options, OPTION_REQUESTED_ADDRESS).unwrap_or(&request[12..16]);
+
+                match <[u8; 4]>::try_from(requested).map(Ipv4Addr::from) {
+                    Ok(requested) if requested != self.lease(hardware_address) => NAK,
+                    _ => ACK,
+                }
+            }
+            _ => return false,
+        };
+
+        let lease = (message_type != NAK).then(|| self.lease(hardware_address));
+
+        reply.fill(0);
+        reply[0] = BOOT_REPLY;
+        // Hardware type, hardware address length, hops, transaction ID, seconds and flags.
+        reply[1..12].copy_from_slice(&request[1..12]);
+        reply[28..44].copy_from_slice(&request[28..44]);
+        reply[236..240].copy_from_slice(&MAGIC_COOKIE);
+
+        // Renewing clients already have an address, which acknowledgements carry over.
+        if message_type == ACK {
+            reply[12..16].copy_from_slice(&request[12..16]);
+        }
+
+        if let Some(lease) = lease {
+            reply[16..20].copy_from_slice(&lease.octets());
+            reply[20..24].copy_from_slice(&self.address.octets());
+        }
+
+        let mut options = Options {
+            buffer: &mut reply[OPTIONS_OFFSET..],
+            len: 0,
+        };
+
+        options.push(OPTION_MESSAGE_TYPE, &[message_type]);
+        options.push(OPTION_SERVER_ID, &self.address.octets());
+
+        if lease.is_some() {
+            options.push(OPTION_LEASE_TIME, &LEASE_SECONDS.to_be_bytes());
+            options.push(OPTION_SUBNET_MASK, &[255, 255, 255, 0]);
+            options.push(OPTION_ROUTER, &self.address.octets());
+            options.push(OPTION_DNS_SERVER, &self.address.octets());
+        }
+
+        options.end();
+
+        true
+    }
+
+    /// Returns the address leased to the client with `hardware_address`, leasing one if needed.
+    fn lease(&mut self, hardware_address: [u8; 6]) -> Ipv4Addr {
+        let index = self
+            .leases
+            .iter()
+            .position(|lease| *lease == Some(hardware_address))
+            .or_else(|| self.leases.iter().position(Option::is_none))
+            .unwrap_or_else(|| {
+                let index = self.next_reused;
+                self.next_reused = (index + 1) % POOL_SIZE;
+                index
+            });
+
+        self.leases[index] = Some(hardware_address);
+
+        let [a, b, c, d] = self.address.octets();
+        Ipv4Addr::new(a, b, c, d.wrapping_add(1 + index as u8))
+    }
+}
+
+struct Options<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Options<'_> {
+    fn push(&mut self, code: u8, value: &[u8]) {
+        self.buffer[self.len] = code;
+        self.buffer[self.len + 1] = value.len() as u8;
+        self.buffer[self.len + 2..self.len + 2 + value.len()].copy_from_slice(value);
+        self.len += 2 + value.len();
+    }
+
+    fn end(&mut self) {
+        self.buffer[self.len] = OPTION_END;
+        self.len += 1;
+    }
+}
+
+/// Returns the value of option `code` in `options`.
+fn option(options: &[u8], code: u8) -> Option<&[u8]> {
+    let mut offset = 0;
+
+    while let Some(&current) = options.get(offset) {
+        match current {
+            OPTION_END => return None,
+            OPTION_PAD => offset += 1,
+            current => {
+                let len = *options.get(offset + 1)? as usize;
+                let value = options.get(offset + 2..offset + 2 + len)?;
+
+                if current == code {
+                    return Some(value);
+                }
+
+                offset += 2 + len;
+            }
+        }
+    }
+
+    None
+}
+
+/// Answers DHCP requests on `stack` until binding the socket fails.
+pub async fn serve(stack: Stack<'_>, address: Ipv4Addr) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 2];
+    let mut rx_buffer = [0; 1024];
+    let mut tx_meta = [PacketMetadata::EMPTY; 2];
+    let mut tx_buffer = [0; 1024];
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+
+    if let Err(err) = socket.bind(SERVER_PORT) {
+        log::error!("failed to bind UDP socket for DHCP: {err:?}");
+        return;
+    }
+
+    let mut server = Server::new(address);
+    let mut request = [0; 576];
+    let mut reply = [0; REPLY_SIZE];
+
+    loop {
+        let len = match socket.recv_from(&mut request).await {
+            Ok((len, _)) => len,
+            Err(err) => {
+                log::warn!("failed to receive DHCP request: {err:?}");
+                continue;
+            }
+        };
+
+        if !server.reply(&request[..len], &mut reply) {
+            continue;
+        }
+
+        // Clients without an address yet only receive broadcasts.
+        if let Err(err) = socket
+            .send_to(&reply, (Ipv4Addr::BROADCAST, CLIENT_PORT))
+            .await
+        {
+            log::warn!("failed to send DHCP reply: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    const ADDRESS: Ipv4Addr = Ipv4Addr::new(192, 168, 4, 1);
+
+    const CLIENT: [u8; 6] = [0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42];
+
+    /// Options of a discover with client identifier, requested address and parameter request list.
+    const DISCOVER_OPTIONS: &[u8] = &[
+        53, 1, 1, //
+        61, 7, 1, 0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42, //
+        50, 4, 0, 0, 0, 0, //
+        55, 4, 1, 3, 6, 42, //
+        255, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
+    /// Options of the request following the offer, with padding between them.
+    const REQUEST_OPTIONS: &[u8] = &[
+        53, 1, 3, //
+        61, 7, 1, 0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42, //
+        50, 4, 192, 168, 4, 2, //
+        0, 0, //
+        54, 4, 192, 168, 4, 1, //
+        55, 4, 1, 3, 6, 42, //
+        255,
+    ];
+
+    /// Returns a request of `client` with transaction ID 0x3D1D and `options`.
+    fn request(client: [u8; 6], options: &[u8]) -> Vec<u8> {
+        let mut request = std::vec![0; OPTIONS_OFFSET];
+        request[..4].copy_from_slice(&[BOOT_REQUEST, 1, 6, 0]);
+        request[4..8].copy_from_slice(&[0x00, 0x00, 0x3D, 0x1D]);
+        request[28..34].copy_from_slice(&client);
+        request[236..240].copy_from_slice(&MAGIC_COOKIE);
+        request.extend_from_slice(options);
+        request
+    }
+
+    /// Returns the reply of `server` to `request`, if any.
+    fn reply_to(server: &mut Server, request: &[u8]) -> Option<[u8; REPLY_SIZE]> {
+        let mut reply = [0; REPLY_SIZE];
+        server.reply(request, &mut reply).then_some(reply)
+    }
+
+    /// Returns the reply to `CLIENT` with `lease` and `options`.
+    fn expected(lease: Option<[u8; 4]>, options: &[u8]) -> [u8; REPLY_SIZE] {
+        let mut reply = [0; REPLY_SIZE];
+        reply[..4].copy_from_slice(&[BOOT_REPLY, 1, 6, 0]);
+        reply[4..8].copy_from_slice(&[0x00, 0x00, 0x3D, 0x1D]);
+
+        if let Some(lease) = lease {
+            reply[16..20].copy_from_slice(&lease);
+            reply[20..24].copy_from_slice(&ADDRESS.octets());
+        }
+
+        reply[28..34].copy_from_slice(&CLIENT);
+        reply[236..240].copy_from_slice(&MAGIC_COOKIE);
+        reply[OPTIONS_OFFSET..OPTIONS_OFFSET + options.len()].copy_from_slice(options);
+        reply
+    }
+
+    fn leased_options(message_type: u8) -> Vec<u8> {
+        std::vec![
+            53,
+            1,
+            message_type, //
+            54,
+            4,
+            192,
+            168,
+            4,
+            1, //
+            51,
+            4,
+            0x00,
+            0x00,
+            0x0E,
+            0x10, //
+            1,
+            4,
+            255,
+            255,
+            255,
+            0, //
+            3,
+            4,
+            192,
+            168,
+            4,
+            1, //
+            6,
+            4,
+            192,
+            168,
+            4,
+            1, //
+            255,
+        ]
+    }
+
+    /// Returns the address offered to `client`.
+    fn offered(server: &mut Server, client: [u8; 6]) -> [u8; 4] {
+        let reply = reply_to(server, &request(client, DISCOVER_OPTIONS)).unwrap();
+        reply[16..20].try_into().unwrap()
+    }
+
+    #[test]
+    fn offers_and_acknowledges_leases() {
+        let mut server = Server::new(ADDRESS);
+
+        assert_eq!(
+            reply_to(&mut server, &request(CLIENT, DISCOVER_OPTIONS)),
+            Some(expected(Some([192, 168, 4, 2]), &leased_options(OFFER)))
+        );
+        assert_eq!(
+            reply_to(&mut server, &request(CLIENT, REQUEST_OPTIONS)),
+            Some(expected(Some([192, 168, 4, 2]), &leased_options(ACK)))
+        );
+
+        // Renewal with the leased address as client address instead of the option.
+        let mut renewal = request(CLIENT, &[53, 1, 3, 255]);
+        renewal[12..16].copy_from_slice(&[192, 168, 4, 2]);
+
+        let reply = reply_to(&mut server, &renewal).unwrap();
+        assert_eq!(reply[12..16], [192, 168, 4, 2]);
+        assert_eq!(reply[16..20], [192, 168, 4, 2]);
+        assert_eq!(reply[OPTIONS_OFFSET + 2], ACK);
+    }
+
+    #[test]
+    fn leases_addresses_per_client() {
+        let mut server = Server::new(ADDRESS);
+        let other = [0x02, 0, 0, 0, 0, 1];
+
+        assert_eq!(offered(&mut server, CLIENT), [192, 168, 4, 2]);
+        assert_eq!(offered(&mut server, other), [192, 168, 4, 3]);
+        assert_eq!(offered(&mut server, CLIENT), [192, 168, 4, 2]);
+
+        // The lease of one client is not acknowledged for another one.
+        let reply = reply_to(&mut server, &request(other, REQUEST_OPTIONS)).unwrap();
+        assert_eq!(reply[16..24], [0; 8]);
+        assert_eq!(reply[OPTIONS_OFFSET + 2], NAK);
+    }
+
+    #[test]
+    fn reuses_leases_once_pool_is_exhausted() {
+        let mut server = Server::new(ADDRESS);
+
+        for index in 0..POOL_SIZE as u8 {
+            assert_eq!(
+                offered(&mut server, [2, 0, 0, 0, 0, index]),
+                [192, 168, 4, 2 + index]
+            );
+        }
+
+        assert_eq!(offered(&mut server, [2, 0, 0, 0, 1, 0]), [192, 168, 4, 2]);
+        assert_eq!(offered(&mut server, [2, 0, 0, 0, 1, 1]), [192, 168, 4, 3]);
+        assert_eq!(offered(&mut server, [2, 0, 0, 0, 1, 0]), [192, 168, 4, 2]);
+    }
+
+    #[test]
+    fn rejects_requests_for_other_addresses() {
+        let mut server = Server::new(ADDRESS);
+        let mut options = REQUEST_OPTIONS.to_vec();
+        options[17] = 9;
+
+        let nak = [53, 1, NAK, 54, 4, 192, 168, 4, 1, 255];
+        assert_eq!(
+            reply_to(&mut server, &request(CLIENT, &options)),
+            Some(expected(None, &nak))
+        );
+    }
+
+    #[test]
+    fn ignores_requests_for_other_servers() {
+        let mut server = Server::new(ADDRESS);
+        let mut options = REQUEST_OPTIONS.to_vec();
+        options[25] = 254;
+
+        assert_eq!(reply_to(&mut server, &request(CLIENT, &options)), None);
+    }
+
+    #[test]
+    fn ignores_malformed_requests() {
+        let mut server = Server::new(ADDRESS);
+        let discover = request(CLIENT, DISCOVER_OPTIONS);
+
+        // Up to the message type option.
+        for len in 0..OPTIONS_OFFSET + 3 {
+            assert_eq!(reply_to(&mut server, &discover[..len]), None, "{len} bytes");
+        }
+
+        let mut reply = discover.clone();
+        reply[0] = BOOT_REPLY;
+        assert_eq!(reply_to(&mut server, &reply), None);
+
+        let mut cookie = discover.clone();
+        cookie[239] = 0;
+        assert_eq!(reply_to(&mut server, &cookie), None);
+
+        // Release, decline and inform are not answered.
+        for message_type in [4, 7, 8] {
+            let options = [53, 1, message_type, 255];
+            assert_eq!(reply_to(&mut server, &request(CLIENT, &options)), None);
+        }
+
+        for options in [
+            &[255, 53, 1, 1][..],
+            &[53, 2, 1, 0, 255],
+            &[53, 5, 1],
+            &[0, 0, 53],
+        ] {
+            assert_eq!(reply_to(&mut server, &request(CLIENT, options)), None);
+        }
+    }
+
+    #[test]
+    fn finds_options() {
+        assert_eq!(option(REQUEST_OPTIONS, 54), Some(&[192, 168, 4, 1][..]));
+        assert_eq!(option(REQUEST_OPTIONS, 55), Some(&[1, 3, 6, 42][..]));
+        assert_eq!(option(REQUEST_OPTIONS, 51), None);
+        assert_eq!(option(&[0, 0, 51, 0], 51), Some(&[][..]));
+        assert_eq!(option(&[12, 4, 1], 12), None);
+        assert_eq!(option(&[], 53), None);
+    }
+}
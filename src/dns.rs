@@ -0,0 +1,273 @@
+//! DNS server of the setup portal answering every name with the address of the access point, so
+//! that clients looking for the internet end up at the settings page.
+
+use core::net::Ipv4Addr;
+
+use embassy_net::Stack;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+
+const PORT: u16 = 53;
+
+/// Largest DNS message over UDP.
+pub const MAX_MESSAGE_SIZE: usize = 512;
+
+const HEADER_SIZE: usize = 12;
+
+const TYPE_A: u16 = 1;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+
+const TTL_SECONDS: u32 = 60;
+
+/// Writes the answer to `query` into `reply`, resolving all names to `address`. Returns the length
+/// of the reply or `None` if `query` is no standard query with a single question.
+pub fn reply(query: &[u8], address: Ipv4Addr, reply: &mut [u8; MAX_MESSAGE_SIZE]) -> Option<usize> {
+    let header = query.get(..HEADER_SIZE)?;
+
+    let is_query = header[2] & 0x80 == 0;
+    let opcode = (header[2] >> 3) & 0x0F;
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+
+    if !is_query || opcode != 0 || questions != 1 {
+        return None;
+    }
+
+    // The name is a sequence of labels ending with an empty one, followed by type and class.
+    let mut offset = HEADER_SIZE;
+
+    loop {
+        let len = *query.get(offset)? as usize;
+
+        // Compression is not allowed in the question of a query.
+        if len & 0xC0 != 0 {
+            return None;
+        }
+
+        offset += 1 + len;
+
+        if len == 0 {
+            break;
+        }
+    }
+
+    let question = query.get(HEADER_SIZE..offset + 4)?;
+    let query_type = u16::from_be_bytes([query[offset], query[offset + 1]]);
+    let class = u16::from_be_bytes([query[offset + 2], query[offset + 3]]);
+
+    let answers = u16::from(matches!(query_type, TYPE_A | TYPE_ANY) && class == CLASS_IN);
+
+    let mut len = 0;
+    let mut write = |bytes: &[u8]| -> Option<()> {
+        reply
+            .get_mut(len..len + bytes.len())?
+            .copy_from_slice(bytes);
+        len += bytes.len();
+        Some(())
+    };
+
+    // ID, flags with response, authoritative answer and the recursion desired bit of the query.
+    write(&header[..2])?;
+    write(&[0x84 | (header[2] & 0x01), 0x00])?;
+    write(&1u16.to_be_bytes())?;
+    write(&answers.to_be_bytes())?;
+    write(&[0; 4])?;
+    write(question)?;
+
+    if answers > 0 {
+        // Pointer to the name in the question.
+        write(&[0xC0, HEADER_SIZE as u8])?;
+        write(&TYPE_A.to_be_bytes())?;
+        write(&CLASS_IN.to_be_bytes())?;
+        write(&TTL_SECONDS.to_be_bytes())?;
+        write(&4u16.to_be_bytes())?;
+        write(&address.octets())?;
+    }
+
+    Some(len)
+}
+
+/// Answers DNS queries on `stack` until binding the socket fails.
+pub async fn serve(stack: Stack<'_>, address: Ipv4Addr) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 4];
+    let mut rx_buffer = [0; 1024];
+    let mut tx_meta = [PacketMetadata::EMPTY; 4];
+    let mut tx_buffer = [0; 1024];
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+
+    if let Err(err) = socket.bind(PORT) {
+        log::error!("failed to bind UDP socket for DNS: {err:?}");
+        return;
+    }
+
+    let mut query = [0; MAX_MESSAGE_SIZE];
+    let mut answer = [0; MAX_MESSAGE_SIZE];
+
+    loop {
+        let (len, meta) = match socket.recv_from(&mut query).await {
+            Ok(received) => received,
+            Err(err) => {
+                log::warn!("failed to receive DNS query: {err:?}");
+                continue;
+            }
+        };
+
+        let Some(answer_len) = reply(&query[..len], address, &mut answer) else {
+            continue;
+        };
+
+        if let Err(err) = socket.send_to(&answer[..answer_len], meta).await {
+            log::warn!("failed to send DNS answer: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: Ipv4Addr = Ipv4Addr::new(192, 168, 4, 1);
+
+    /// Query for the A record of `captive.apple.com` with recursion desired, as sent by macOS.
+    const QUERY: &[u8] = &[
+        0x1A, 0x2B, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        7, b'c', b'a', b'p', b't', b'i', b'v', b'e', 5, b'a', b'p', b'p', b'l', b'e', 3, b'c',
+        b'o', b'm', 0, //
+        0x00, 0x01, 0x00, 0x01,
+    ];
+
+    const ANSWER: &[u8] = &[
+        0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x04, 192, 168, 4, 1,
+    ];
+
+    fn reply_to(query: &[u8]) -> Option<std::vec::Vec<u8>> {
+        let mut buffer = [0; MAX_MESSAGE_SIZE];
+        let len = reply(query, ADDRESS, &mut buffer)?;
+
+        Some(buffer[..len].to_vec())
+    }
+
+    #[test]
+    fn answers_address_queries() {
+        let header = [
+            0x1A, 0x2B, 0x85, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let expected = [&header, &QUERY[12..], ANSWER].concat();
+
+        assert_eq!(reply_to(QUERY).unwrap(), expected);
+
+        // Without recursion desired and for any type.
+        let mut query = QUERY.to_vec();
+        query[2] = 0x00;
+        query[32] = 0xFF;
+
+        let reply = reply_to(&query).unwrap();
+        assert_eq!(reply[2..4], [0x84, 0x00]);
+        assert_eq!(reply[12..35], query[12..]);
+        assert_eq!(reply[35..], *ANSWER);
+    }
+
+    #[test]
+    fn answers_other_types_without_records() {
+        // AAAA record.
+        let mut query = QUERY.to_vec();
+        query[32] = 28;
+
+        let header = [
+            0x1A, 0x2B, 0x85, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(reply_to(&query).unwrap(), [&header, &query[12..]].concat());
+
+        // Chaos class.
+        let mut query = QUERY.to_vec();
+        query[34] = 3;
+
+        assert_eq!(reply_to(&query).unwrap()[6..8], [0, 0]);
+    }
+
+    #[test]
+    fn ignores_additional_records() {
+        // OPT record with a UDP payload size of 1232 bytes, as added by most resolvers.
+        let opt = [
+            0x00, 0x00, 0x29, 0x04, 0xD0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut query = [QUERY, &opt].concat();
+        query[11] = 1;
+
+        let header = [
+            0x1A, 0x2B, 0x85, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let expected = [&header, &QUERY[12..], ANSWER].concat();
+
+        assert_eq!(reply_to(&query).unwrap(), expected);
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        // Response.
+        let mut query = QUERY.to_vec();
+        query[2] |= 0x80;
+        assert_eq!(reply_to(&query), None);
+
+        // Inverse query.
+        let mut query = QUERY.to_vec();
+        query[2] |= 0x08;
+        assert_eq!(reply_to(&query), None);
+
+        // No and two questions.
+        for questions in [0, 2] {
+            let mut query = QUERY.to_vec();
+            query[5] = questions;
+            assert_eq!(reply_to(&query), None);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_queries() {
+        for len in 0..QUERY.len() {
+            assert_eq!(reply_to(&QUERY[..len]), None, "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        // Pointer to the start of the name in place of `captive`.
+        let mut query = QUERY[..12].to_vec();
+        query.extend_from_slice(&[0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01]);
+        assert_eq!(reply_to(&query), None);
+
+        // Label with the reserved 0b01 prefix.
+        let mut query = QUERY.to_vec();
+        query[12] = 0x47;
+        assert_eq!(reply_to(&query), None);
+
+        // Label running past the end of the message.
+        let mut query = QUERY.to_vec();
+        query[20] = 63;
+        assert_eq!(reply_to(&query), None);
+    }
+
+    #[test]
+    fn rejects_queries_with_too_long_replies() {
+        // Name of 495 bytes, leaving no room for the answer in a reply of 512 bytes.
+        let mut query = QUERY[..12].to_vec();
+
+        for _ in 0..7 {
+            query.push(63);
+            query.extend_from_slice(&[b'a'; 63]);
+        }
+
+        query.push(46);
+        query.extend_from_slice(&[b'a'; 46]);
+        query.extend_from_slice(&[0, 0x00, 0x01, 0x00, 0x01]);
+
+        assert_eq!(query.len(), MAX_MESSAGE_SIZE);
+        assert_eq!(reply_to(&query), None);
+    }
+}
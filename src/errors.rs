@@ -2,7 +2,7 @@
 /// context to the error.
 #[derive(Debug)]
 pub enum Error {
-    /// HTTP client error.
+    /// HTTP client or server error.
     Http(&'static str),
     /// JSON parse error.
     ParseJson(&'static str),
@@ -12,4 +12,12 @@ pub enum Error {
     ParseUtf8,
     /// Generic date/time error.
     DateTime(&'static str),
+    /// E-paper display error.
+    Epd(&'static str),
+    /// Wi-Fi controller error.
+    Wifi(&'static str),
+    /// Flash storage error.
+    Storage(&'static str),
+    /// Invalid configuration value.
+    Config(&'static str),
 }
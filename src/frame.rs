@@ -0,0 +1,340 @@
+//! Tracking of the frames shown on the display to refresh only the widgets that changed.
+//!
+//! Instead of keeping a copy of the last 48 KB frame, only hashes of it and of each widget area
+//! are kept.
+
+extern crate alloc;
+
+use core::hash::{BuildHasher, Hasher};
+
+use alloc::vec::Vec;
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use jiff::civil::Date;
+use rapidhash::fast;
+
+use crate::layout;
+
+/// Width and height of the display in the panel's native, landscape orientation, in which the
+/// frame buffer is laid out.
+const NATIVE_WIDTH: u32 = layout::HEIGHT;
+const NATIVE_HEIGHT: u32 = layout::WIDTH;
+
+const ROW_BYTES: usize = (NATIVE_WIDTH / 8) as usize;
+
+/// Number of partial updates after which a full refresh clears the ghosting they leave behind.
+const FULL_REFRESH_INTERVAL: usize = 12;
+
+/// Largest region in bytes updated partially, larger changes refresh the whole display.
+const MAX_PARTIAL_BYTES: usize = 8192;
+
+/// How to bring the display up to date with a new frame.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Update<const N: usize> {
+    /// Refresh the whole display.
+    Full,
+    /// Refresh only the given regions in native display coordinates, possibly none.
+    Partial(heapless::Vec<Rectangle, N>),
+}
+
+/// Hashes of the frame last sent to the display and of its `N` widget areas.
+pub struct FrameHistory<const N: usize> {
+    previous: Option<(u64, [u64; N])>,
+    /// Hash of the previous frame before drawing the parts that change on every update.
+    previous_content: Option<u64>,
+    partial_updates: usize,
+    last_full_refresh: Option<Date>,
+}
+
+impl<const N: usize> Default for FrameHistory<N> {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            previous_content: None,
+            partial_updates: 0,
+            last_full_refresh: None,
+        }
+    }
+}
+
+impl<const N: usize> FrameHistory<N> {
+    /// Returns `true` if `buffer`, drawn without parts that change on every update such as the
+    /// status bar, equals the content of the previous frame and the display was already fully
+    /// refreshed `today`, so that showing it can be skipped.
+    pub fn content_unchanged(&mut self, buffer: &[u8], today: Date) -> bool {
+        let content = fast::SeedableState::new(0).hash_one(buffer);
+
+        self.previous_content.replace(content) == Some(content)
+            && self.last_full_refresh == Some(today)
+    }
+
+    /// Compares `buffer` to the previous frame and returns how to show it. `areas` are the widget
+    /// areas in display coordinates and must be the same on every call. The first update of a day
+    /// is always a full refresh.
+    pub fn update(&mut self, buffer: &[u8], areas: &[Rectangle; N], today: Date) -> Update<N> {
+        let state = fast::SeedableState::new(0);
+        let frame = state.hash_one(buffer);
+        let regions = areas.map(|area| native_region(&area));
+        let hashes = regions.map(|region| hash_region(&state, buffer, &region));
+
+        let Some((previous_frame, previous_hashes)) = self.previous.replace((frame, hashes)) else {
+            return self.full(today);
+        };
+
+        if self.partial_updates >= FULL_REFRESH_INTERVAL || self.last_full_refresh != Some(today) {
+            return self.full(today);
+        }
+
+        let dirty: heapless::Vec<Rectangle, N> = regions
+            .into_iter()
+            .zip(hashes.into_iter().zip(previous_hashes))
+            .filter(|(region, (hash, previous))| hash != previous && !region.is_zero_sized())
+            .map(|(region, _)| region)
+            .collect();
+
+        // Something outside of all widget areas changed.
+        if dirty.is_empty() && frame != previous_frame {
+            return self.full(today);
+        }
+
+        if dirty
+            .iter()
+            .any(|region| region_bytes(region) > MAX_PARTIAL_BYTES)
+        {
+            return self.full(today);
+        }
+
+        self.partial_updates += 1;
+        Update::Partial(dirty)
+    }
+
+    /// Forgets the previous frame so that the next one is shown with a full refresh, e.g. after
+    /// showing a frame failed.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+        self.previous_content = None;
+    }
+
+    fn full(&mut self, today: Date) -> Update<N> {
+        self.partial_updates = 0;
+        self.last_full_refresh = Some(today);
+        Update::Full
+    }
+}
+
+/// Returns the part of `buffer` covered by `region`, row by row as expected for partial updates.
+pub fn region_buffer(buffer: &[u8], region: &Rectangle) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(region_bytes(region));
+
+    for row in region_rows(buffer, region) {
+        bytes.extend_from_slice(row);
+    }
+
+    bytes
+}
+
+/// Maps `area` of the rotated display to the panel's native coordinates, widened to whole bytes.
+fn native_region(area: &Rectangle) -> Rectangle {
+    let top = (area.top_left.y.max(0) as u32).min(NATIVE_WIDTH);
+    let bottom = (top + area.size.height).min(NATIVE_WIDTH);
+
+    // The rotated y axis runs from right to left along the native x axis.
+    let left = (NATIVE_WIDTH - bottom) / 8 * 8;
+    let right = (NATIVE_WIDTH - top).div_ceil(8) * 8;
+
+    let x = area.top_left.x.max(0) as u32;
+    let height = area.size.width.min(NATIVE_HEIGHT.saturating_sub(x));
+
+    Rectangle::new(
+        Point::new(left as i32, x as i32),
+        Size::new(right - left, height),
+    )
+}
+
+fn region_bytes(region: &Rectangle) -> usize {
+    (region.size.width / 8 * region.size.height) as usize
+}
+
+fn region_rows<'a>(buffer: &'a [u8], region: &Rectangle) -> impl Iterator<Item = &'a [u8]> {
+    let start = region.top_left.x as usize / 8;
+    let width = region.size.width as usize / 8;
+    let top = region.top_left.y as usize;
+
+    (top..top + region.size.height as usize).map(move |y| {
+        let offset = y * ROW_BYTES + start;
+        &buffer[offset..offset + width]
+    })
+}
+
+fn hash_region(state: &fast::SeedableState, buffer: &[u8], region: &Rectangle) -> u64 {
+    let mut hasher = state.build_hasher();
+
+    for row in region_rows(buffer, region) {
+        hasher.write(row);
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_SIZE: usize = ROW_BYTES * NATIVE_HEIGHT as usize;
+
+    fn area(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+        Rectangle::new(Point::new(x, y), Size::new(width, height))
+    }
+
+    fn today() -> Date {
+        jiff::civil::date(2025, 1, 15)
+    }
+
+    /// Widget areas at the top and bottom left of the rotated display.
+    const AREAS: [Rectangle; 2] = [
+        Rectangle::new(Point::new(0, 0), Size::new(100, 16)),
+        Rectangle::new(Point::new(0, 784), Size::new(100, 16)),
+    ];
+
+    fn partial<const N: usize>(regions: &[Rectangle]) -> Update<N> {
+        Update::Partial(heapless::Vec::from_slice(regions).unwrap())
+    }
+
+    #[test]
+    fn maps_rotated_areas_to_native_regions() {
+        // The status bar at the bottom is at the left of the panel.
+        assert_eq!(native_region(&area(0, 784, 480, 16)), area(0, 0, 16, 480));
+        assert_eq!(native_region(&area(36, 0, 216, 88)), area(712, 36, 88, 216));
+
+        // Widened to whole bytes.
+        assert_eq!(native_region(&area(10, 3, 20, 10)), area(784, 10, 16, 20));
+
+        // Cut off at the edges of the display.
+        assert_eq!(native_region(&area(470, 790, 20, 20)), area(0, 470, 16, 10));
+        assert_eq!(native_region(&area(-5, -5, 10, 10)), area(784, 0, 16, 10));
+    }
+
+    #[test]
+    fn native_regions_cover_rotated_areas() {
+        for area in [
+            area(0, 0, 32, 64),
+            area(36, 0, 216, 88),
+            area(252, 0, 228, 72),
+            area(0, 88, 480, 16),
+            area(0, 104, 480, 586),
+            area(3, 5, 7, 11),
+        ] {
+            let region = native_region(&area);
+
+            // Rotating by 90° maps the point (x, y) to (799 - y, x) of the panel.
+            for corner in [area.top_left, area.bottom_right().unwrap()] {
+                let native = Point::new(NATIVE_WIDTH as i32 - 1 - corner.y, corner.x);
+
+                assert!(region.contains(native), "{area:?} {region:?}");
+            }
+
+            assert_eq!(region.top_left.x % 8, 0);
+            assert_eq!(region.size.width % 8, 0);
+        }
+    }
+
+    #[test]
+    fn copies_region_rows() {
+        let mut buffer = std::vec![0; FRAME_SIZE];
+        buffer[ROW_BYTES + 98] = 1;
+        buffer[2 * ROW_BYTES + 99] = 2;
+
+        let region = native_region(&area(1, 0, 2, 16));
+
+        assert_eq!(region_buffer(&buffer, &region), [1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn refreshes_changed_areas_partially() {
+        let mut frames = FrameHistory::default();
+        let mut buffer = std::vec![0; FRAME_SIZE];
+
+        assert_eq!(frames.update(&buffer, &AREAS, today()), Update::Full);
+
+        // Unchanged.
+        assert_eq!(frames.update(&buffer, &AREAS, today()), partial(&[]));
+
+        // Top left of the rotated display, at the right end of the first native row.
+        buffer[ROW_BYTES - 1] = 0xFF;
+
+        assert_eq!(
+            frames.update(&buffer, &AREAS, today()),
+            partial(&[native_region(&AREAS[0])])
+        );
+
+        buffer[0] = 0xFF;
+        buffer[ROW_BYTES - 1] = 0;
+
+        assert_eq!(
+            frames.update(&buffer, &AREAS, today()),
+            partial(&[native_region(&AREAS[0]), native_region(&AREAS[1])])
+        );
+    }
+
+    #[test]
+    fn forces_full_refresh() {
+        let mut frames = FrameHistory::default();
+        let mut buffer = std::vec![0; FRAME_SIZE];
+
+        frames.update(&buffer, &AREAS, today());
+
+        // Outside of all widget areas.
+        buffer[FRAME_SIZE / 2] = 0xFF;
+        assert_eq!(frames.update(&buffer, &AREAS, today()), Update::Full);
+
+        // On the first update of a day.
+        let tomorrow = today().tomorrow().unwrap();
+        assert_eq!(frames.update(&buffer, &AREAS, tomorrow), Update::Full);
+        assert_eq!(frames.update(&buffer, &AREAS, tomorrow), partial(&[]));
+
+        // After showing a frame failed.
+        frames.invalidate();
+        assert_eq!(frames.update(&buffer, &AREAS, tomorrow), Update::Full);
+
+        // After too many partial updates.
+        for _ in 0..FULL_REFRESH_INTERVAL {
+            assert_eq!(frames.update(&buffer, &AREAS, tomorrow), partial(&[]));
+        }
+
+        assert_eq!(frames.update(&buffer, &AREAS, tomorrow), Update::Full);
+
+        // For changes of large areas.
+        let large = [area(0, 0, 480, 200)];
+        let mut frames = FrameHistory::default();
+
+        frames.update(&buffer, &large, today());
+        buffer[ROW_BYTES - 1] = 0xFF;
+
+        assert_eq!(frames.update(&buffer, &large, today()), Update::Full);
+    }
+
+    #[test]
+    fn skips_unchanged_content() {
+        let mut frames = FrameHistory::<2>::default();
+        let mut buffer = std::vec![0; FRAME_SIZE];
+
+        assert!(!frames.content_unchanged(&buffer, today()));
+
+        // Not yet fully refreshed today.
+        assert!(!frames.content_unchanged(&buffer, today()));
+
+        frames.update(&buffer, &AREAS, today());
+
+        assert!(frames.content_unchanged(&buffer, today()));
+        assert!(!frames.content_unchanged(&buffer, today().tomorrow().unwrap()));
+
+        buffer[0] = 0xFF;
+
+        assert!(!frames.content_unchanged(&buffer, today()));
+        assert!(frames.content_unchanged(&buffer, today()));
+
+        frames.invalidate();
+
+        assert!(!frames.content_unchanged(&buffer, today()));
+    }
+}
@@ -0,0 +1,562 @@
+//! Minimal HTTP/1.1 server side: reading requests, decoding form data and writing responses.
+//!
+//! Only what the settings pages need is supported: one request per connection, bodies sized by
+//! `Content-Length` and no chunked encoding.
+
+use core::fmt;
+use core::net::Ipv4Addr;
+
+use embedded_io_async::{Read, Write};
+use heapless::{String, format};
+
+use crate::errors::Error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Other,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Request<'a> {
+    pub method: Method,
+    /// Path without the query.
+    pub path: &'a str,
+    /// Query without the leading `?`, empty if there is none.
+    pub query: &'a str,
+    /// Values of the `Host`, `Origin` and `Authorization` headers, if present.
+    pub host: Option<&'a str>,
+    pub origin: Option<&'a str>,
+    pub authorization: Option<&'a str>,
+    pub body: &'a [u8],
+}
+
+impl Request<'_> {
+    /// Returns whether the request names the device known as `host_name` in the local network or
+    /// at `address` as its host and comes from one of its own pages, if from a page at all. This
+    /// keeps other sites from using browsers in the network to reach the device, by posting forms
+    /// or by resolving their own names to its address.
+    pub fn is_same_origin(&self, host_name: &str, address: Option<Ipv4Addr>) -> bool {
+        let is_own_host = |host: &str| {
+            let host = host.strip_suffix(":80").unwrap_or(host);
+            let name = host.strip_suffix(".local").unwrap_or(host);
+
+            name.eq_ignore_ascii_case(host_name)
+                || address.is_some_and(|address| host.parse() == Ok(address))
+        };
+
+        self.host.is_none_or(is_own_host)
+            && self
+                .origin
+                .is_none_or(|origin| origin.strip_prefix("http://").is_some_and(is_own_host))
+    }
+
+    /// Returns whether the request carries `Basic` credentials with `password` and any user name,
+    /// or `password` is empty.
+    pub fn is_authorized(&self, password: &str) -> bool {
+        if password.is_empty() {
+            return true;
+        }
+
+        let Some(credentials) = self
+            .authorization
+            .and_then(|authorization| authorization.trim().split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("basic"))
+            .and_then(|(_, credentials)| decode_base64(credentials.trim()))
+        else {
+            return false;
+        };
+
+        credentials
+            .iter()
+            .position(|&byte| byte == b':')
+            .is_some_and(|separator| &credentials[separator + 1..] == password.as_bytes())
+    }
+}
+
+/// Parses the request in `buffer`, returning `None` if it is not complete yet.
+pub fn parse_request(buffer: &[u8]) -> Result<Option<Request<'_>>, Error> {
+    let Some(head_len) = buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+        return Ok(None);
+    };
+
+    let head = core::str::from_utf8(&buffer[..head_len]).map_err(|_| Error::ParseUtf8)?;
+    let mut lines = head.split("\r\n");
+
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+
+    let method = match request_line.next() {
+        Some("GET") => Method::Get,
+        Some("POST") => Method::Post,
+        _ => Method::Other,
+    };
+
+    let target = request_line
+        .next()
+        .ok_or(Error::Http("request line without target"))?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    let mut host = None;
+    let mut origin = None;
+    let mut authorization = None;
+
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let value = value.trim();
+
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse()
+                .map_err(|_| Error::Http("invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("host") {
+            host = Some(value);
+        } else if name.eq_ignore_ascii_case("origin") {
+            origin = Some(value);
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value);
+        }
+    }
+
+    let body_start = head_len + 4;
+
+    Ok(buffer
+        .get(body_start..body_start.saturating_add(content_length))
+        .map(|body| Request {
+            method,
+            path,
+            query,
+            host,
+            origin,
+            authorization,
+            body,
+        }))
+}
+
+/// Reads a request from `reader` into `buffer`, which limits its size.
+pub async fn read_request<'b, R: Read>(
+    reader: &mut R,
+    buffer: &'b mut [u8],
+) -> Result<Request<'b>, Error> {
+    let mut len = 0;
+
+    while parse_request(&buffer[..len])?.is_none() {
+        if len == buffer.len() {
+            return Err(Error::Http("request too large"));
+        }
+
+        match reader.read(&mut buffer[len..]).await {
+            Ok(0) | Err(_) => return Err(Error::Http("connection closed before end of request")),
+            Ok(read) => len += read,
+        }
+    }
+
+    parse_request(&buffer[..len])?.ok_or(Error::Http("incomplete request"))
+}
+
+/// Writes a complete response with `status` such as `200 OK`, closing the connection afterwards.
+pub async fn write_response<W: Write>(
+    writer: &mut W,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), Error> {
+    write_head(writer, status, content_type, body.len()).await?;
+
+    writer
+        .write_all(body)
+        .await
+        .map_err(|_| Error::Http("failed to write response"))?;
+    writer
+        .flush()
+        .await
+        .map_err(|_| Error::Http("failed to write response"))
+}
+
+/// Writes the head of a response with a body of `content_length` bytes, for bodies written in
+/// parts afterwards.
+pub async fn write_head<W: Write>(
+    writer: &mut W,
+    status: &str,
+    content_type: &str,
+    content_length: usize,
+) -> Result<(), Error> {
+    let head: String<256> = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {content_length}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n"
+    )
+    .map_err(|_| Error::Http("response head too long"))?;
+
+    writer
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|_| Error::Http("failed to write response"))
+}
+
+/// Writes a response redirecting to `location`.
+pub async fn write_redirect<W: Write>(writer: &mut W, location: &str) -> Result<(), Error> {
+    let head: String<256> = format!(
+        "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    )
+    .map_err(|_| Error::Http("redirect location too long"))?;
+
+    writer
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|_| Error::Http("failed to write response"))?;
+    writer
+        .flush()
+        .await
+        .map_err(|_| Error::Http("failed to write response"))
+}
+
+/// Writes a response asking for the password of `realm` with `Basic` authentication.
+pub async fn write_unauthorized<W: Write>(writer: &mut W, realm: &str) -> Result<(), Error> {
+    let body = "password required";
+    let head: String<256> = format!(
+        "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"{realm}\", charset=\"UTF-8\"\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .map_err(|_| Error::Http("realm too long"))?;
+
+    writer
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|_| Error::Http("failed to write response"))?;
+    writer
+        .write_all(body.as_bytes())
+        .await
+        .map_err(|_| Error::Http("failed to write response"))?;
+    writer
+        .flush()
+        .await
+        .map_err(|_| Error::Http("failed to write response"))
+}
+
+/// Splits `application/x-www-form-urlencoded` data into name and value pairs, both still encoded.
+pub fn form_fields(form: &str) -> impl Iterator<Item = (&str, &str)> {
+    form.split('&')
+        .filter(|field| !field.is_empty())
+        .map(|field| field.split_once('=').unwrap_or((field, "")))
+}
+
+/// Decodes a form value, with `+` for spaces and `%XX` for other bytes.
+pub fn decode<const N: usize>(value: &str) -> Result<String<N>, Error> {
+    let mut bytes = heapless::Vec::<u8, N>::new();
+    let mut input = value.bytes();
+
+    while let Some(byte) = input.next() {
+        let decoded = match byte {
+            b'+' => b' ',
+            b'%' => {
+                let high = input.next().and_then(hex_digit);
+                let low = input.next().and_then(hex_digit);
+
+                high.zip(low)
+                    .map(|(high, low)| (high << 4) | low)
+                    .ok_or(Error::Http("invalid percent encoding"))?
+            }
+            byte => byte,
+        };
+
+        bytes
+            .push(decoded)
+            .map_err(|_| Error::Http("form value too long"))?;
+    }
+
+    String::from_utf8(bytes).map_err(|_| Error::ParseUtf8)
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Maximum size of decoded `Basic` credentials.
+const MAX_CREDENTIALS_SIZE: usize = 192;
+
+/// Decodes standard base64 with optional padding, as used by `Basic` credentials.
+fn decode_base64(encoded: &str) -> Option<heapless::Vec<u8, MAX_CREDENTIALS_SIZE>> {
+    let mut decoded = heapless::Vec::new();
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+
+    for byte in encoded.trim_end_matches('=').bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        bits = ((bits << 6) | u32::from(value)) & 0xFFFF;
+        bit_count += 6;
+
+        if bit_count >= 8 {
+            bit_count -= 8;
+            decoded.push((bits >> bit_count) as u8).ok()?;
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Displays the wrapped text escaped for HTML text and attribute values.
+pub struct Escaped<'a>(pub &'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&#39;")?,
+                c => fmt::Write::write_char(f, c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requests() {
+        let request = parse_request(b"GET /status?verbose=1 HTTP/1.1\r\nHost: dashboard\r\n\r\n")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.path, "/status");
+        assert_eq!(request.query, "verbose=1");
+        assert_eq!(request.body, b"");
+
+        let request = parse_request(b"POST /config HTTP/1.1\r\ncontent-length: 7\r\n\r\na=1&b=2")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.path, "/config");
+        assert_eq!(request.query, "");
+        assert_eq!(request.body, b"a=1&b=2");
+
+        let request = parse_request(b"DELETE / HTTP/1.1\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.method, Method::Other);
+    }
+
+    #[test]
+    fn parses_headers() {
+        let request = parse_request(
+            b"POST /config HTTP/1.1\r\nHOST: dashboard.local\r\norigin:http://dashboard.local\r\n\
+            Authorization:  Basic YWRtaW46c2VjcmV0  \r\nX-Other: 1\r\n\r\n",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(request.host, Some("dashboard.local"));
+        assert_eq!(request.origin, Some("http://dashboard.local"));
+        assert_eq!(request.authorization, Some("Basic YWRtaW46c2VjcmV0"));
+
+        let request = parse_request(b"GET / HTTP/1.1\r\n\r\n").unwrap().unwrap();
+
+        assert_eq!(request.host, None);
+        assert_eq!(request.origin, None);
+        assert_eq!(request.authorization, None);
+    }
+
+    fn request<'a>(host: Option<&'a str>, origin: Option<&'a str>) -> Request<'a> {
+        Request {
+            method: Method::Post,
+            path: "/config",
+            query: "",
+            host,
+            origin,
+            authorization: None,
+            body: b"",
+        }
+    }
+
+    #[test]
+    fn accepts_requests_for_own_host() {
+        let address = Some(Ipv4Addr::new(192, 168, 1, 23));
+
+        for (host, origin) in [
+            (None, None),
+            (Some("dashboard.local"), None),
+            (Some("Dashboard.local:80"), None),
+            (Some("dashboard"), None),
+            (Some("192.168.1.23"), None),
+            (Some("dashboard.local"), Some("http://dashboard.local")),
+            (Some("192.168.1.23"), Some("http://192.168.1.23:80")),
+            (None, Some("http://dashboard.local")),
+        ] {
+            assert!(
+                request(host, origin).is_same_origin("dashboard", address),
+                "{host:?} {origin:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_requests_for_other_hosts() {
+        let address = Some(Ipv4Addr::new(192, 168, 1, 23));
+
+        for (host, origin) in [
+            (Some("evil.example"), None),
+            (Some("dashboard.local.evil.example"), None),
+            (Some("dashboard.local:8080"), None),
+            (Some("192.168.1.24"), None),
+            (Some("dashboard.local"), Some("http://evil.example")),
+            (Some("dashboard.local"), Some("https://dashboard.local")),
+            (Some("dashboard.local"), Some("null")),
+            (None, Some("http://evil.example")),
+        ] {
+            assert!(
+                !request(host, origin).is_same_origin("dashboard", address),
+                "{host:?} {origin:?}"
+            );
+        }
+
+        assert!(!request(Some("192.168.1.23"), None).is_same_origin("dashboard", None));
+    }
+
+    #[test]
+    fn checks_basic_credentials() {
+        let authorized = |authorization, password| {
+            Request {
+                authorization,
+                ..request(None, None)
+            }
+            .is_authorized(password)
+        };
+
+        // "admin:secret", ":secret", "admin:se:cret" and "admin:secreT".
+        assert!(authorized(Some("Basic YWRtaW46c2VjcmV0"), "secret"));
+        assert!(authorized(Some("basic OnNlY3JldA=="), "secret"));
+        assert!(authorized(Some("Basic YWRtaW46c2U6Y3JldA=="), "se:cret"));
+        assert!(!authorized(Some("Basic YWRtaW46c2VjcmVU"), "secret"));
+        assert!(!authorized(Some("Bearer YWRtaW46c2VjcmV0"), "secret"));
+        assert!(!authorized(Some("Basic YWRtaW46c2VjcmV0!"), "secret"));
+        assert!(!authorized(Some("Basic"), "secret"));
+        assert!(!authorized(None, "secret"));
+
+        // Without a password, everyone is authorized.
+        assert!(authorized(None, ""));
+    }
+
+    #[test]
+    fn decodes_base64() {
+        for (encoded, decoded) in [
+            ("", &b""[..]),
+            ("Zg==", b"f"),
+            ("Zm8=", b"fo"),
+            ("Zm9v", b"foo"),
+            ("Zm9vYg", b"foob"),
+            ("w7w+/w==", b"\xC3\xBC\x3E\xFF"),
+        ] {
+            assert_eq!(decode_base64(encoded).unwrap(), decoded, "{encoded}");
+        }
+
+        assert!(decode_base64("Zm9v YmFy").is_none());
+        assert!(decode_base64(&"QUFB".repeat(MAX_CREDENTIALS_SIZE / 3 + 1)).is_none());
+    }
+
+    #[test]
+    fn waits_for_complete_requests() {
+        assert!(parse_request(b"GET / HTTP/1.1\r\n").unwrap().is_none());
+        assert!(
+            parse_request(b"POST / HTTP/1.1\r\nContent-Length: 7\r\n\r\na=1")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        assert!(parse_request(b"GET\r\n\r\n").is_err());
+        assert!(parse_request(b"POST / HTTP/1.1\r\nContent-Length: x\r\n\r\n").is_err());
+        assert!(parse_request(b"GET /\xFF HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn reads_requests_arriving_in_parts() {
+        /// Reader returning the parts in turn, each cut to fit the buffer.
+        struct Parts<'a>(&'a [&'a [u8]]);
+
+        impl embedded_io_async::ErrorType for Parts<'_> {
+            type Error = core::convert::Infallible;
+        }
+
+        impl Read for Parts<'_> {
+            async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+                let Some((part, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+
+                let len = part.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&part[..len]);
+                self.0 = rest;
+
+                Ok(len)
+            }
+        }
+
+        let parts: &[&[u8]] = &[b"POST / HTTP/1.1\r\nContent-", b"Length: 3\r\n\r\n", b"a=1"];
+        let read = |parts, buffer: &mut [u8]| {
+            embassy_futures::block_on(read_request(&mut Parts(parts), buffer))
+                .map(|request| request.body.len())
+        };
+
+        assert_eq!(read(parts, &mut [0; 256]).unwrap(), 3);
+        assert!(read(parts, &mut [0; 16]).is_err());
+        assert!(read(&parts[..2], &mut [0; 256]).is_err());
+    }
+
+    #[test]
+    fn splits_form_fields() {
+        let fields: Vec<_> = form_fields("a=1&&b=&c&d=x%3Dy").collect();
+
+        assert_eq!(fields, [("a", "1"), ("b", ""), ("c", ""), ("d", "x%3Dy")]);
+    }
+
+    #[test]
+    fn decodes_form_values() {
+        assert_eq!(decode::<32>("Home+Net").unwrap(), "Home Net");
+        assert_eq!(
+            decode::<32>("https%3A%2F%2Fx.org%2Fa.ics").unwrap(),
+            "https://x.org/a.ics"
+        );
+        assert_eq!(decode::<32>("a%2bb%2Bc").unwrap(), "a+b+c");
+        assert_eq!(decode::<32>("K%C3%BCche").unwrap(), "Küche");
+        assert_eq!(decode::<32>("").unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_invalid_form_values() {
+        assert!(decode::<32>("%").is_err());
+        assert!(decode::<32>("%4").is_err());
+        assert!(decode::<32>("%GG").is_err());
+        assert!(decode::<32>("%FF").is_err());
+        assert!(decode::<4>("12345").is_err());
+        assert!(decode::<4>("%20%20%20%20%20").is_err());
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            std::format!("{}", Escaped("<a href=\"x\">Tom & Jerry's</a>")),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use embedded_io_async::Read;
 use embedded_nal_async::{Dns, TcpConnect};
 use reqwless::client::HttpClient;
+use serde::{Deserialize, Serialize};
 
 use crate::clock;
 use crate::errors::Error;
 
 const MAX_SUMMARY_LENGTH: usize = 32;
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Event {
     pub start: Either,
     pub end: Either,
@@ -15,10 +16,10 @@ pub struct Event {
 }
 
 /// Enum to discriminate between full-day and timed events.
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 pub enum Either {
     /// Event starts or ends on a particular time.
-    DateTime(jiff::Zoned),
+    DateTime(#[serde(with = "crate::cache::fixed_offset")] jiff::Zoned),
     /// Event covers a full day.
     Date(jiff::civil::Date),
 }
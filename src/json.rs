@@ -0,0 +1,594 @@
+use core::fmt;
+
+use embedded_io_async::Read;
+use heapless::Vec;
+
+use crate::errors::Error;
+
+/// Size of the chunks read from the underlying reader.
+const CHUNK_SIZE: usize = 64;
+
+/// A single JSON token. Commas and colons are consumed silently, a string followed by a colon is
+/// reported as [`Token::Key`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Key(&'a str),
+    String(&'a str),
+    Number(&'a str),
+    Bool(bool),
+    Null,
+}
+
+impl<'a> Token<'a> {
+    /// Returns the numeric value of this token or NaN for `null`.
+    pub fn as_f32(self) -> Result<f32, Error> {
+        match self {
+            Token::Number(number) => number
+                .parse()
+                .map_err(|_| Error::ParseJson("failed to parse number")),
+            Token::Null => Ok(f32::NAN),
+            _ => Err(Error::ParseJson("expected number")),
+        }
+    }
+
+    pub fn as_u8(self) -> Result<u8, Error> {
+        match self {
+            Token::Number(number) => number
+                .parse()
+                .map_err(|_| Error::ParseJson("failed to parse integer")),
+            _ => Err(Error::ParseJson("expected integer")),
+        }
+    }
+
+    pub fn as_str(self) -> Result<&'a str, Error> {
+        match self {
+            Token::String(string) => Ok(string),
+            _ => Err(Error::ParseJson("expected string")),
+        }
+    }
+}
+
+/// Incremental JSON tokenizer reading from an async reader, e.g. an HTTP response body, so that
+/// responses never have to be kept in memory as a whole.
+///
+/// Strings and numbers longer than `N` bytes are truncated.
+pub struct Tokenizer<R, const N: usize = 64> {
+    reader: R,
+    chunk: [u8; CHUNK_SIZE],
+    pos: usize,
+    len: usize,
+    text: Vec<u8, N>,
+}
+
+impl<R: Read, const N: usize> Tokenizer<R, N> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chunk: [0; CHUNK_SIZE],
+            pos: 0,
+            len: 0,
+            text: Vec::new(),
+        }
+    }
+
+    /// Returns the next token.
+    pub async fn next(&mut self) -> Result<Token<'_>, Error> {
+        let byte = self
+            .skip_separators()
+            .await?
+            .ok_or(Error::ParseJson("unexpected end of JSON"))?;
+
+        self.pos += 1;
+
+        match byte {
+            b'{' => Ok(Token::BeginObject),
+            b'}' => Ok(Token::EndObject),
+            b'[' => Ok(Token::BeginArray),
+            b']' => Ok(Token::EndArray),
+            b'"' => {
+                self.read_string().await?;
+
+                if self.skip_whitespace().await? == Some(b':') {
+                    self.pos += 1;
+                    Ok(Token::Key(self.text()))
+                } else {
+                    Ok(Token::String(self.text()))
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                self.text.clear();
+                self.push_text(byte);
+
+                while let Some(byte) = self.peek().await? {
+                    if !matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                        break;
+                    }
+
+                    self.pos += 1;
+                    self.push_text(byte);
+                }
+
+                Ok(Token::Number(self.text()))
+            }
+            b't' => self.expect_literal(b"rue", Token::Bool(true)).await,
+            b'f' => self.expect_literal(b"alse", Token::Bool(false)).await,
+            b'n' => self.expect_literal(b"ull", Token::Null).await,
+            _ => Err(Error::ParseJson("unexpected character")),
+        }
+    }
+
+    /// Consumes the opening brace of an object.
+    pub async fn begin_object(&mut self) -> Result<(), Error> {
+        match self.next().await? {
+            Token::BeginObject => Ok(()),
+            _ => Err(Error::ParseJson("expected object")),
+        }
+    }
+
+    /// Consumes the opening bracket of an array.
+    pub async fn begin_array(&mut self) -> Result<(), Error> {
+        match self.next().await? {
+            Token::BeginArray => Ok(()),
+            _ => Err(Error::ParseJson("expected array")),
+        }
+    }
+
+    /// Returns the next key of the current object or `None` at its end.
+    pub async fn next_key(&mut self) -> Result<Option<&str>, Error> {
+        match self.next().await? {
+            Token::Key(key) => Ok(Some(key)),
+            Token::EndObject => Ok(None),
+            _ => Err(Error::ParseJson("expected key")),
+        }
+    }
+
+    /// Advances past the first key named `key` at any depth.
+    pub async fn seek_key(&mut self, key: &str) -> Result<(), Error> {
+        loop {
+            if let Token::Key(next) = self.next().await?
+                && next == key
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Skips the next value including all nested values.
+    pub async fn skip_value(&mut self) -> Result<(), Error> {
+        let mut depth = 0usize;
+
+        loop {
+            match self.next().await? {
+                Token::BeginObject | Token::BeginArray => depth += 1,
+                Token::EndObject | Token::EndArray => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or(Error::ParseJson("unexpected end of value"))?;
+                }
+                Token::Key(_) => continue,
+                _ => {}
+            }
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads an array of scalar values into `values` using `parse` to convert each token and
+    /// returns the number of values stored. Values exceeding the length of `values` are skipped.
+    pub async fn read_array<T, F>(&mut self, values: &mut [T], mut parse: F) -> Result<usize, Error>
+    where
+        F: FnMut(Token<'_>) -> Result<T, Error>,
+    {
+        self.begin_array().await?;
+
+        let mut count = 0;
+
+        loop {
+            let token = self.next().await?;
+
+            match token {
+                Token::EndArray => return Ok(count),
+                Token::BeginObject | Token::BeginArray => {
+                    return Err(Error::ParseJson("expected scalar array element"));
+                }
+                token => {
+                    if let Some(value) = values.get_mut(count) {
+                        *value = parse(token)?;
+                        count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the next object or array verbatim into `buffer`, e.g. to deserialize a small element
+    /// of a large array with `serde_json_core`. Returns `None` at the end of the enclosing array or
+    /// object.
+    pub async fn raw_value<'b>(&mut self, buffer: &'b mut [u8]) -> Result<Option<&'b [u8]>, Error> {
+        match self.skip_separators().await? {
+            Some(b'{' | b'[') => {}
+            Some(b']' | b'}') => {
+                self.pos += 1;
+                return Ok(None);
+            }
+            _ => return Err(Error::ParseJson("expected object or array")),
+        }
+
+        let mut size = 0;
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let byte = self.next_byte().await?;
+
+            *buffer
+                .get_mut(size)
+                .ok_or(Error::ParseJson("JSON value exceeds buffer"))? = byte;
+
+            size += 1;
+
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+            } else {
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => depth -= 1,
+                    _ => {}
+                }
+
+                if depth == 0 {
+                    return Ok(Some(&buffer[..size]));
+                }
+            }
+        }
+    }
+
+    async fn read_string(&mut self) -> Result<(), Error> {
+        self.text.clear();
+
+        loop {
+            let byte = self.next_byte().await?;
+
+            match byte {
+                b'"' => return Ok(()),
+                b'\\' => {
+                    let escaped = match self.next_byte().await? {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'r' => '\r',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let mut code = 0u32;
+
+                            for _ in 0..4 {
+                                let digit = char::from(self.next_byte().await?)
+                                    .to_digit(16)
+                                    .ok_or(Error::ParseJson("invalid unicode escape"))?;
+
+                                code = code * 16 + digit;
+                            }
+
+                            // Surrogate pairs are not combined.
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        other => char::from(other),
+                    };
+
+                    for byte in escaped.encode_utf8(&mut [0; 4]).bytes() {
+                        self.push_text(byte);
+                    }
+                }
+                byte => self.push_text(byte),
+            }
+        }
+    }
+
+    async fn expect_literal<'a>(
+        &mut self,
+        rest: &[u8],
+        token: Token<'a>,
+    ) -> Result<Token<'a>, Error> {
+        for expected in rest {
+            if self.next_byte().await? != *expected {
+                return Err(Error::ParseJson("invalid literal"));
+            }
+        }
+
+        Ok(token)
+    }
+
+    /// Append a byte to the current string or number, dropping it once `text` is full.
+    fn push_text(&mut self, byte: u8) {
+        let _ = self.text.push(byte);
+    }
+
+    /// Returns the current string or number. Text truncated in the middle of a multi-byte
+    /// character is cut back to the last complete one.
+    fn text(&self) -> &str {
+        match core::str::from_utf8(&self.text) {
+            Ok(text) => text,
+            Err(err) => core::str::from_utf8(&self.text[..err.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+
+    async fn skip_whitespace(&mut self) -> Result<Option<u8>, Error> {
+        while let Some(byte) = self.peek().await? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+
+            self.pos += 1;
+        }
+
+        Ok(None)
+    }
+
+    async fn skip_separators(&mut self) -> Result<Option<u8>, Error> {
+        while let Some(byte) = self.peek().await? {
+            if !byte.is_ascii_whitespace() && byte != b',' && byte != b':' {
+                return Ok(Some(byte));
+            }
+
+            self.pos += 1;
+        }
+
+        Ok(None)
+    }
+
+    async fn next_byte(&mut self) -> Result<u8, Error> {
+        let byte = self
+            .peek()
+            .await?
+            .ok_or(Error::ParseJson("unexpected end of JSON"))?;
+
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Returns the next byte without consuming it or `None` at the end of input.
+    async fn peek(&mut self) -> Result<Option<u8>, Error> {
+        if self.pos == self.len {
+            self.len = self
+                .reader
+                .read(&mut self.chunk)
+                .await
+                .map_err(|_| Error::Http("failed to read response"))?;
+
+            self.pos = 0;
+        }
+
+        Ok(self.chunk[..self.len].get(self.pos).copied())
+    }
+}
+
+/// Displays the wrapped text as a quoted JSON string.
+pub struct Quoted<'a>(pub &'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => fmt::Write::write_char(f, c)?,
+            }
+        }
+
+        f.write_str("\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embassy_futures::block_on;
+
+    /// Reader returning a single byte per read, so that every token is split across reads.
+    struct Bytewise<'a>(&'a [u8]);
+
+    impl embedded_io_async::ErrorType for Bytewise<'_> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Read for Bytewise<'_> {
+        async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+            let Some((byte, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+
+            buffer[0] = *byte;
+            self.0 = rest;
+
+            Ok(1)
+        }
+    }
+
+    fn bytewise(json: &[u8]) -> Tokenizer<Bytewise<'_>> {
+        Tokenizer::new(Bytewise(json))
+    }
+
+    #[test]
+    fn reads_tokens() {
+        let mut tokenizer = bytewise(b" {\"a\" : [1, -2.5e3, true, false, null], \"b\": \"c\"}\n");
+
+        for expected in [
+            Token::BeginObject,
+            Token::Key("a"),
+            Token::BeginArray,
+            Token::Number("1"),
+            Token::Number("-2.5e3"),
+            Token::Bool(true),
+            Token::Bool(false),
+            Token::Null,
+            Token::EndArray,
+            Token::Key("b"),
+            Token::String("c"),
+            Token::EndObject,
+        ] {
+            assert_eq!(block_on(tokenizer.next()).unwrap(), expected);
+        }
+
+        assert!(block_on(tokenizer.next()).is_err());
+        assert!(block_on(bytewise(b"nul").next()).is_err());
+        assert!(block_on(bytewise(b"trie").next()).is_err());
+        assert!(block_on(bytewise(b"?").next()).is_err());
+    }
+
+    #[test]
+    fn reads_tokens_split_across_chunks() {
+        // Places a key, a number and a literal across the end of the first chunk.
+        for padding in CHUNK_SIZE - 12..CHUNK_SIZE {
+            let mut json = std::vec![b' '; padding];
+            json.extend_from_slice(b"{\"temperature\":-12.75,\"day\":true}");
+
+            let mut tokenizer = Tokenizer::<_>::new(json.as_slice());
+
+            assert_eq!(block_on(tokenizer.next()).unwrap(), Token::BeginObject);
+            assert_eq!(
+                block_on(tokenizer.next()).unwrap(),
+                Token::Key("temperature")
+            );
+            assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Number("-12.75"));
+            assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Key("day"));
+            assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Bool(true));
+            assert_eq!(block_on(tokenizer.next()).unwrap(), Token::EndObject);
+        }
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        let mut tokenizer = bytewise(br#"["a\"b\\c\/d\ne", "\u00e9\u20AC", "\ud83d"]"#);
+
+        block_on(tokenizer.begin_array()).unwrap();
+
+        assert_eq!(
+            block_on(tokenizer.next()).unwrap(),
+            Token::String("a\"b\\c/d\ne")
+        );
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::String("é€"));
+        assert_eq!(
+            block_on(tokenizer.next()).unwrap(),
+            Token::String("\u{fffd}")
+        );
+
+        assert!(block_on(bytewise(br#""\u00zz""#).next()).is_err());
+        assert!(block_on(bytewise(br#""unterminated"#).next()).is_err());
+    }
+
+    #[test]
+    fn truncates_long_text() {
+        let mut tokenizer =
+            Tokenizer::<_, 4>::new(Bytewise(r#"["abcdef", "aéé", 123456]"#.as_bytes()));
+
+        block_on(tokenizer.begin_array()).unwrap();
+
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::String("abcd"));
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::String("aé"));
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Number("1234"));
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::EndArray);
+    }
+
+    #[test]
+    fn seeks_keys_at_any_depth() {
+        let json = br#"{"a": {"b": "d"}, "c": [{"d": 2}], "d": 3}"#;
+
+        let mut tokenizer = bytewise(json);
+        block_on(tokenizer.seek_key("d")).unwrap();
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Number("2"));
+
+        // Seeking continues from the current position.
+        block_on(tokenizer.seek_key("d")).unwrap();
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Number("3"));
+
+        assert!(block_on(bytewise(json).seek_key("e")).is_err());
+    }
+
+    #[test]
+    fn skips_nested_values() {
+        let mut tokenizer =
+            bytewise(br#"{"skip": {"a": [1, {"b": []}], "c": "}]"}, "scalar": 1, "next": true}"#);
+
+        block_on(tokenizer.begin_object()).unwrap();
+
+        assert_eq!(block_on(tokenizer.next_key()).unwrap(), Some("skip"));
+        block_on(tokenizer.skip_value()).unwrap();
+
+        assert_eq!(block_on(tokenizer.next_key()).unwrap(), Some("scalar"));
+        block_on(tokenizer.skip_value()).unwrap();
+
+        assert_eq!(block_on(tokenizer.next_key()).unwrap(), Some("next"));
+        assert_eq!(block_on(tokenizer.next()).unwrap(), Token::Bool(true));
+        assert_eq!(block_on(tokenizer.next_key()).unwrap(), None);
+
+        assert!(block_on(bytewise(b"]").skip_value()).is_err());
+        assert!(block_on(bytewise(b"[[1]").skip_value()).is_err());
+    }
+
+    #[test]
+    fn reads_arrays_up_to_capacity() {
+        let mut tokenizer = bytewise(br#"{"a": [1, 2, 3, 4], "b": []}"#);
+        let mut values = [0; 2];
+
+        block_on(tokenizer.seek_key("a")).unwrap();
+        let count = block_on(tokenizer.read_array(&mut values, |token| token.as_u8())).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(values, [1, 2]);
+
+        // The remaining values were consumed.
+        assert_eq!(block_on(tokenizer.next_key()).unwrap(), Some("b"));
+        assert_eq!(
+            block_on(tokenizer.read_array(&mut values, |token| token.as_u8())).unwrap(),
+            0
+        );
+
+        let read = |json| block_on(bytewise(json).read_array(&mut [0; 4], |token| token.as_u8()));
+
+        assert!(read(b"[1, [2]]").is_err());
+        assert!(read(b"[1, \"2\"]").is_err());
+        assert!(read(b"{}").is_err());
+        assert!(read(b"[1, 2").is_err());
+    }
+
+    #[test]
+    fn copies_raw_values() {
+        let mut tokenizer = bytewise(br#"[{"a": "}]\"", "b": [1]}, [2, 3]]"#);
+        let mut buffer = [0; 32];
+
+        block_on(tokenizer.begin_array()).unwrap();
+
+        assert_eq!(
+            block_on(tokenizer.raw_value(&mut buffer)).unwrap(),
+            Some(br#"{"a": "}]\"", "b": [1]}"#.as_slice())
+        );
+        assert_eq!(
+            block_on(tokenizer.raw_value(&mut buffer)).unwrap(),
+            Some(b"[2, 3]".as_slice())
+        );
+        assert_eq!(block_on(tokenizer.raw_value(&mut buffer)).unwrap(), None);
+
+        assert!(block_on(bytewise(b"[1]").raw_value(&mut [0; 3])).is_ok());
+        assert!(block_on(bytewise(b"[1]").raw_value(&mut [0; 2])).is_err());
+        assert!(block_on(bytewise(b"1").raw_value(&mut buffer)).is_err());
+        assert!(block_on(bytewise(b"[1").raw_value(&mut buffer)).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Placement of the dashboard widgets on the rotated 480x800 pixel display.
+//!
+//! Each widget is drawn into its own [`Rectangle`] with coordinates relative to its top left
+//! corner, so widgets can be moved without touching their drawing code.
+
+use core::str::FromStr;
+
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+
+use crate::errors::Error;
+
+/// Width and height of the display in its rotated, portrait orientation.
+pub const WIDTH: u32 = 480;
+pub const HEIGHT: u32 = 800;
+
+/// Areas of all widgets in display coordinates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub date: Rectangle,
+    pub hourly_weather: Rectangle,
+    /// Shared by the daily forecast and the weather alert banner replacing it.
+    pub daily_weather: Rectangle,
+    pub air_quality: Rectangle,
+    pub agenda: Rectangle,
+    pub todos: Rectangle,
+    pub status_bar: Rectangle,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            date: area(0, 0, 32, 64),
+            hourly_weather: area(36, 0, 216, 88),
+            daily_weather: area(252, 0, WIDTH - 252, 72),
+            air_quality: area(0, 88, WIDTH, 16),
+            agenda: area(0, 104, WIDTH, 586),
+            todos: area(0, 690, WIDTH, 94),
+            status_bar: area(0, 784, WIDTH, 16),
+        }
+    }
+}
+
+impl Layout {
+    /// Returns the agenda area extended by the part of the todo area not needed for
+    /// `todos_height` pixels of todos at its bottom, if the todo area directly follows the agenda.
+    pub fn agenda_above_todos(&self, todos_height: u32) -> Rectangle {
+        let mut agenda = self.agenda;
+
+        if self.todos.top_left == agenda.top_left + Point::new(0, agenda.size.height as i32)
+            && self.todos.size.width == agenda.size.width
+        {
+            agenda.size.height += self.todos.size.height.saturating_sub(todos_height);
+        }
+
+        agenda
+    }
+
+    /// Returns the areas of all widgets.
+    pub fn areas(&self) -> [Rectangle; 7] {
+        [
+            self.date,
+            self.hourly_weather,
+            self.daily_weather,
+            self.air_quality,
+            self.agenda,
+            self.todos,
+            self.status_bar,
+        ]
+    }
+
+    fn area_mut(&mut self, widget: &str) -> Option<&mut Rectangle> {
+        match widget {
+            "date" => Some(&mut self.date),
+            "hourly_weather" => Some(&mut self.hourly_weather),
+            "daily_weather" => Some(&mut self.daily_weather),
+            "air_quality" => Some(&mut self.air_quality),
+            "agenda" => Some(&mut self.agenda),
+            "todos" => Some(&mut self.todos),
+            "status_bar" => Some(&mut self.status_bar),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a layout from the default one with areas overridden by `;` separated entries of the form
+/// `widget=x,y,width,height`, e.g. `todos=0,104,480,94;agenda=0,198,480,586`.
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut layout = Layout::default();
+
+        for entry in s
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (widget, values) = entry
+                .split_once('=')
+                .ok_or(Error::Config("expected widget=x,y,width,height"))?;
+
+            let mut values = values.split(',').map(|value| value.trim().parse::<u32>());
+            let mut next = || {
+                values
+                    .next()
+                    .and_then(Result::ok)
+                    .ok_or(Error::Config("invalid widget area"))
+            };
+
+            let (x, y, width, height) = (next()?, next()?, next()?, next()?);
+
+            if values.next().is_some() {
+                return Err(Error::Config("too many values in widget area"));
+            }
+
+            if x.saturating_add(width) > WIDTH || y.saturating_add(height) > HEIGHT {
+                return Err(Error::Config("widget area exceeds display"));
+            }
+
+            *layout
+                .area_mut(widget.trim())
+                .ok_or(Error::Config("unknown widget"))? = area(x, y, width, height);
+        }
+
+        Ok(layout)
+    }
+}
+
+const fn area(x: u32, y: u32, width: u32, height: u32) -> Rectangle {
+    Rectangle::new(Point::new(x as i32, y as i32), Size::new(width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(layout: &str) -> &'static str {
+        match layout.parse::<Layout>() {
+            Err(Error::Config(message)) => message,
+            result => panic!("expected configuration error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_overridden_areas() {
+        let layout: Layout = " todos = 0, 104, 480, 94 ;agenda=0,198,480,586;"
+            .parse()
+            .unwrap();
+
+        assert_eq!(layout.todos, area(0, 104, 480, 94));
+        assert_eq!(layout.agenda, area(0, 198, 480, 586));
+        assert_eq!(layout.date, Layout::default().date);
+
+        assert_eq!("".parse::<Layout>().unwrap(), Layout::default());
+    }
+
+    #[test]
+    fn rejects_invalid_areas() {
+        assert_eq!(error("todos"), "expected widget=x,y,width,height");
+        assert_eq!(error("todos=0,104,480"), "invalid widget area");
+        assert_eq!(error("todos=0,104,480,-94"), "invalid widget area");
+        assert_eq!(
+            error("todos=0,104,480,94,1"),
+            "too many values in widget area"
+        );
+        assert_eq!(
+            error("todos=0,104,480,94,"),
+            "too many values in widget area"
+        );
+        assert_eq!(error("todos=0,720,480,94"), "widget area exceeds display");
+        assert_eq!(error("clock=0,0,48,16"), "unknown widget");
+    }
+
+    #[test]
+    fn extends_agenda_above_missing_todos() {
+        let layout = Layout::default();
+
+        assert_eq!(layout.agenda_above_todos(94).size.height, 586);
+        assert_eq!(layout.agenda_above_todos(34).size.height, 646);
+        assert_eq!(layout.agenda_above_todos(0).size.height, 680);
+
+        // Only if the todos directly follow the agenda.
+        let layout: Layout = "todos=0,0,480,94".parse().unwrap();
+
+        assert_eq!(layout.agenda_above_todos(0).size.height, 586);
+    }
+}
@@ -1,11 +1,33 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
+pub mod air_quality;
+pub mod alerts;
+pub mod cache;
 pub mod clock;
+pub mod config;
+pub mod dhcp;
+pub mod dns;
 pub mod errors;
+pub mod frame;
+pub mod http;
 pub mod icons;
 pub mod ics;
+pub mod json;
+pub mod layout;
+pub mod locale;
+pub mod mdns;
 pub mod ntp;
+#[cfg(feature = "esp")]
+pub mod portal;
+pub mod schedule;
+pub mod screenshot;
+#[cfg(feature = "esp")]
+pub mod server;
+pub mod status;
+pub mod storage;
 pub mod todo;
 pub mod ui;
+pub mod units;
 pub mod weather;
+#[cfg(feature = "esp")]
 pub mod wifi;
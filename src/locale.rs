@@ -0,0 +1,269 @@
+//! Translations of all text drawn on the display.
+//!
+//! `build.rs` checks that the fonts contain every character of the string literals in this file,
+//! so keep user visible text here.
+
+use core::str::FromStr;
+
+use jiff::civil::Weekday;
+use serde::{Deserialize, Serialize};
+
+use crate::air_quality::{AqiCategory, Pollen};
+use crate::errors::Error;
+use crate::status::Source;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    De,
+    En,
+    Fr,
+    Nl,
+    Es,
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "de" => Ok(Self::De),
+            "en" => Ok(Self::En),
+            "fr" => Ok(Self::Fr),
+            "nl" => Ok(Self::Nl),
+            "es" => Ok(Self::Es),
+            _ => Err(Error::Config("unknown locale")),
+        }
+    }
+}
+
+impl Locale {
+    pub const ALL: [Locale; 5] = [Locale::De, Locale::En, Locale::Fr, Locale::Nl, Locale::Es];
+
+    /// Returns the code the locale is selected with, the inverse of [`Locale::from_str`].
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::De => "de",
+            Locale::En => "en",
+            Locale::Fr => "fr",
+            Locale::Nl => "nl",
+            Locale::Es => "es",
+        }
+    }
+
+    pub fn weekday(self, weekday: Weekday) -> &'static str {
+        let names = match self {
+            Locale::De => ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+            Locale::En => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            Locale::Fr => ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+            Locale::Nl => ["ma", "di", "wo", "do", "vr", "za", "zo"],
+            Locale::Es => ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+        };
+
+        names[weekday.to_monday_zero_offset() as usize]
+    }
+
+    /// Returns the three letter abbreviation of `month` in upper case.
+    pub fn month(self, month: i8) -> &'static str {
+        let names = match self {
+            Locale::De => [
+                "JAN", "FEB", "MÄR", "APR", "MAI", "JUN", "JUL", "AUG", "SEP", "OKT", "NOV", "DEZ",
+            ],
+            Locale::En => [
+                "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+            ],
+            Locale::Fr => [
+                "JAN", "FÉV", "MAR", "AVR", "MAI", "JUN", "JUL", "AOÛ", "SEP", "OCT", "NOV", "DÉC",
+            ],
+            Locale::Nl => [
+                "JAN", "FEB", "MRT", "APR", "MEI", "JUN", "JUL", "AUG", "SEP", "OKT", "NOV", "DEC",
+            ],
+            Locale::Es => [
+                "ENE", "FEB", "MAR", "ABR", "MAY", "JUN", "JUL", "AGO", "SEP", "OCT", "NOV", "DIC",
+            ],
+        };
+
+        usize::try_from(month - 1)
+            .ok()
+            .and_then(|index| names.get(index))
+            .copied()
+            .unwrap_or("???")
+    }
+
+    /// `strtime` format of a day and month without year.
+    pub fn date_format(self) -> &'static str {
+        match self {
+            Locale::De => "%d.%m.",
+            Locale::En => "%m/%d",
+            Locale::Fr | Locale::Es => "%d/%m",
+            Locale::Nl => "%d-%m",
+        }
+    }
+
+    /// Short label for tomorrow, at most six characters.
+    pub fn tomorrow(self) -> &'static str {
+        match self {
+            Locale::De | Locale::Nl => "morgen",
+            Locale::En => "tmrw",
+            Locale::Fr => "dem.",
+            Locale::Es => "mañana",
+        }
+    }
+
+    /// Preposition for the end of a range, e.g. "bis" in "bis Di, 12.03.".
+    pub fn until(self) -> &'static str {
+        match self {
+            Locale::De => "bis",
+            Locale::En => "until",
+            Locale::Fr => "jusqu'à",
+            Locale::Nl => "tot",
+            Locale::Es => "hasta",
+        }
+    }
+
+    pub fn until_further_notice(self) -> &'static str {
+        match self {
+            Locale::De => "bis auf Weiteres",
+            Locale::En => "until further notice",
+            Locale::Fr => "jusqu'à nouvel ordre",
+            Locale::Nl => "tot nader order",
+            Locale::Es => "hasta nuevo aviso",
+        }
+    }
+
+    /// Word following the number of events that did not fit, e.g. "weitere" in "+3 weitere".
+    pub fn more(self) -> &'static str {
+        match self {
+            Locale::De => "weitere",
+            Locale::En => "more",
+            Locale::Fr => "de plus",
+            Locale::Nl => "meer",
+            Locale::Es => "más",
+        }
+    }
+
+    /// Title of the error screen.
+    pub fn error(self) -> &'static str {
+        match self {
+            Locale::De => "Fehler",
+            Locale::En | Locale::Es => "Error",
+            Locale::Fr => "Erreur",
+            Locale::Nl => "Fout",
+        }
+    }
+
+    pub fn last_update(self) -> &'static str {
+        match self {
+            Locale::De => "Letzte Aktualisierung",
+            Locale::En => "Last update",
+            Locale::Fr => "Dernière mise à jour",
+            Locale::Nl => "Laatste update",
+            Locale::Es => "Última actualización",
+        }
+    }
+
+    pub fn never(self) -> &'static str {
+        match self {
+            Locale::De => "nie",
+            Locale::En => "never",
+            Locale::Fr => "jamais",
+            Locale::Nl => "nooit",
+            Locale::Es => "nunca",
+        }
+    }
+
+    /// Label of a data source in the status bar, at most four characters.
+    pub fn source(self, source: Source) -> &'static str {
+        // In declaration order of `Source`.
+        let names = match self {
+            Locale::De => ["Std", "Warn", "Tag", "Luft", "Kal", "Todo"],
+            Locale::En => ["Hour", "Warn", "Day", "Air", "Cal", "Todo"],
+            Locale::Fr => ["Heur", "Alrt", "Jour", "Air", "Agen", "Tâch"],
+            Locale::Nl => ["Uur", "Waar", "Dag", "Lcht", "Agen", "Todo"],
+            Locale::Es => ["Hora", "Alrt", "Día", "Aire", "Cal", "Tar"],
+        };
+
+        names[source as usize]
+    }
+
+    /// Prefix of the time data was last fetched, e.g. "stale since" in "stale since 14:00".
+    pub fn stale_since(self) -> &'static str {
+        match self {
+            Locale::De => "veraltet seit",
+            Locale::En => "stale since",
+            Locale::Fr => "périmé depuis",
+            Locale::Nl => "verouderd sinds",
+            Locale::Es => "sin actualizar desde",
+        }
+    }
+
+    pub fn air(self) -> &'static str {
+        match self {
+            Locale::De => "Luft",
+            Locale::En | Locale::Fr => "Air",
+            Locale::Nl => "Lucht",
+            Locale::Es => "Aire",
+        }
+    }
+
+    pub fn aqi_category(self, category: AqiCategory) -> &'static str {
+        // In declaration order of `AqiCategory`.
+        let names = match self {
+            Locale::De => [
+                "gut",
+                "befriedigend",
+                "mäßig",
+                "schlecht",
+                "sehr schlecht",
+                "extrem schlecht",
+            ],
+            Locale::En => [
+                "good",
+                "fair",
+                "moderate",
+                "poor",
+                "very poor",
+                "extremely poor",
+            ],
+            Locale::Fr => [
+                "bon",
+                "moyen",
+                "dégradé",
+                "mauvais",
+                "très mauvais",
+                "extr. mauvais",
+            ],
+            Locale::Nl => [
+                "goed",
+                "redelijk",
+                "matig",
+                "slecht",
+                "zeer slecht",
+                "extreem slecht",
+            ],
+            Locale::Es => [
+                "buena",
+                "razonable",
+                "regular",
+                "mala",
+                "muy mala",
+                "extr. mala",
+            ],
+        };
+
+        names[category as usize]
+    }
+
+    pub fn pollen(self, pollen: Pollen) -> &'static str {
+        // In declaration order of `Pollen`.
+        let names = match self {
+            Locale::De => ["Erle", "Birke", "Gräser", "Ambrosia"],
+            Locale::En => ["Alder", "Birch", "Grass", "Ragweed"],
+            Locale::Fr => ["Aulne", "Bouleau", "Graminées", "Ambroisie"],
+            Locale::Nl => ["Els", "Berk", "Grassen", "Ambrosia"],
+            Locale::Es => ["Aliso", "Abedul", "Gramíneas", "Ambrosía"],
+        };
+
+        names[pollen as usize]
+    }
+}
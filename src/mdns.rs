@@ -0,0 +1,673 @@
+//! mDNS responder making the dashboard reachable as `<host name>.local` and announcing its HTTP
+//! server as `_http._tcp` service, so that it can be found without looking up DHCP leases.
+
+use core::net::Ipv4Addr;
+
+use embassy_net::Stack;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+use embassy_time::{Duration, Timer};
+
+const PORT: u16 = 5353;
+
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+
+/// Port of the HTTP server announced in the service record.
+const HTTP_PORT: u16 = 80;
+
+/// Largest mDNS message handled, queries with many known answers are truncated by the socket.
+pub const MAX_MESSAGE_SIZE: usize = 512;
+
+const HEADER_SIZE: usize = 12;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+
+/// Set in the class of records that are only ever announced by this host.
+const CACHE_FLUSH: u16 = 0x8000;
+
+/// Set in the class of questions asking for a unicast response.
+const UNICAST_RESPONSE: u16 = 0x8000;
+
+const TTL_SECONDS: u32 = 120;
+
+/// TTL of records in responses to legacy queries, which are cached like regular DNS records.
+const LEGACY_TTL_SECONDS: u32 = 10;
+
+/// Maximum number of labels in a name handled.
+const MAX_LABELS: usize = 8;
+
+const SERVICE: [&[u8]; 3] = [b"_http", b"_tcp", b"local"];
+
+/// Records this host answers for.
+#[derive(Clone, Copy, Default)]
+struct Records {
+    address: bool,
+    pointer: bool,
+    service: bool,
+    text: bool,
+}
+
+impl Records {
+    const ALL: Self = Self {
+        address: true,
+        pointer: true,
+        service: true,
+        text: true,
+    };
+
+    fn any(self) -> bool {
+        self.address || self.pointer || self.service || self.text
+    }
+
+    /// Returns the records that help resolving these ones but are not included yet.
+    fn additional(self) -> Self {
+        Self {
+            address: !self.address && (self.pointer || self.service),
+            pointer: false,
+            service: !self.service && self.pointer,
+            text: !self.text && self.pointer,
+        }
+    }
+}
+
+/// Writes the response to `query` for `host_name` at `address` into `response`. Returns the length
+/// of the response or `None` if `query` asks for nothing this host knows about.
+///
+/// With `legacy` set for queries from other ports than the mDNS one, the response is a plain DNS
+/// response with the ID and question of the query.
+pub fn respond(
+    query: &[u8],
+    host_name: &str,
+    address: Ipv4Addr,
+    legacy: bool,
+    response: &mut [u8; MAX_MESSAGE_SIZE],
+) -> Option<usize> {
+    let header = query.get(..HEADER_SIZE)?;
+
+    let is_query = header[2] & 0x80 == 0;
+    let opcode = (header[2] >> 3) & 0x0F;
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+
+    if !is_query || opcode != 0 {
+        return None;
+    }
+
+    let mut answers = Records::default();
+    let mut offset = HEADER_SIZE;
+    // Legacy queries have a single question, echoed in the response.
+    let mut question = &query[HEADER_SIZE..HEADER_SIZE];
+
+    for _ in 0..questions {
+        let (labels, end) = read_name(query, offset)?;
+        let fields = query.get(end..end + 4)?;
+        let query_type = u16::from_be_bytes([fields[0], fields[1]]);
+        let class = u16::from_be_bytes([fields[2], fields[3]]) & !UNICAST_RESPONSE;
+
+        if legacy {
+            question = &query[offset..end + 4];
+        }
+
+        offset = end + 4;
+
+        if class != CLASS_IN {
+            continue;
+        }
+
+        let asks = |record_type| query_type == record_type || query_type == TYPE_ANY;
+        let host_name = host_name.as_bytes();
+
+        if is_name(&labels, &[host_name, b"local"]) {
+            answers.address |= asks(TYPE_A);
+        } else if is_name(&labels, &SERVICE) {
+            answers.pointer |= asks(TYPE_PTR);
+        } else if is_name(&labels, &[host_name, SERVICE[0], SERVICE[1], SERVICE[2]]) {
+            answers.service |= asks(TYPE_SRV);
+            answers.text |= asks(TYPE_TXT);
+        }
+    }
+
+    if !answers.any() {
+        return None;
+    }
+
+    let id = if legacy {
+        [header[0], header[1]]
+    } else {
+        [0; 2]
+    };
+
+    write_response(response, id, question, legacy, host_name, address, answers)
+}
+
+/// Writes an unsolicited response announcing all records into `response` and returns its length.
+pub fn announce(
+    host_name: &str,
+    address: Ipv4Addr,
+    response: &mut [u8; MAX_MESSAGE_SIZE],
+) -> Option<usize> {
+    write_response(
+        response,
+        [0; 2],
+        &[],
+        false,
+        host_name,
+        address,
+        Records::ALL,
+    )
+}
+
+fn write_response(
+    response: &mut [u8; MAX_MESSAGE_SIZE],
+    id: [u8; 2],
+    question: &[u8],
+    legacy: bool,
+    host_name: &str,
+    address: Ipv4Addr,
+    answers: Records,
+) -> Option<usize> {
+    let additional = answers.additional();
+    let count = |records: Records| {
+        [
+            records.address,
+            records.pointer,
+            records.service,
+            records.text,
+        ]
+        .into_iter()
+        .filter(|&record| record)
+        .count() as u16
+    };
+
+    let mut message = Message {
+        buffer: response,
+        len: 0,
+        legacy,
+    };
+
+    // ID, flags with response and authoritative answer, and the number of records in each section.
+    message.write(&id)?;
+    message.write(&[0x84, 0x00])?;
+    message.write(&u16::from(!question.is_empty()).to_be_bytes())?;
+    message.write(&count(answers).to_be_bytes())?;
+    message.write(&[0; 2])?;
+    message.write(&count(additional).to_be_bytes())?;
+    message.write(question)?;
+
+    for records in [answers, additional] {
+        message.write_records(records, host_name, address)?;
+    }
+
+    Some(message.len)
+}
+
+struct Message<'a> {
+    buffer: &'a mut [u8; MAX_MESSAGE_SIZE],
+    len: usize,
+    /// Whether the message answers a legacy query.
+    legacy: bool,
+}
+
+impl Message<'_> {
+    fn write(&mut self, bytes: &[u8]) -> Option<()> {
+        self.buffer
+            .get_mut(self.len..self.len + bytes.len())?
+            .copy_from_slice(bytes);
+        self.len += bytes.len();
+        Some(())
+    }
+
+    fn write_name(&mut self, labels: &[&[u8]]) -> Option<()> {
+        for label in labels {
+            self.write(&[label.len() as u8])?;
+            self.write(label)?;
+        }
+
+        self.write(&[0])
+    }
+
+    /// Writes the type, class and TTL of a record followed by the length of its data.
+    fn write_record(&mut self, record_type: u16, cache_flush: bool, data_len: usize) -> Option<()> {
+        // Legacy resolvers do not know about the cache flush bit.
+        let (class, ttl) = if self.legacy {
+            (CLASS_IN, LEGACY_TTL_SECONDS)
+        } else if cache_flush {
+            (CLASS_IN | CACHE_FLUSH, TTL_SECONDS)
+        } else {
+            (CLASS_IN, TTL_SECONDS)
+        };
+
+        self.write(&record_type.to_be_bytes())?;
+        self.write(&class.to_be_bytes())?;
+        self.write(&ttl.to_be_bytes())?;
+        self.write(&(data_len as u16).to_be_bytes())
+    }
+
+    fn write_records(
+        &mut self,
+        records: Records,
+        host_name: &str,
+        address: Ipv4Addr,
+    ) -> Option<()> {
+        let host_name = host_name.as_bytes();
+        let host = [host_name, b"local"];
+        let instance = [host_name, SERVICE[0], SERVICE[1], SERVICE[2]];
+
+        if records.address {
+            self.write_name(&host)?;
+            self.write_record(TYPE_A, true, 4)?;
+            self.write(&address.octets())?;
+        }
+
+        if records.pointer {
+            self.write_name(&SERVICE)?;
+            // Other hosts announce instances of the same service.
+            self.write_record(TYPE_PTR, false, name_len(&instance))?;
+            self.write_name(&instance)?;
+        }
+
+        if records.service {
+            self.write_name(&instance)?;
+            self.write_record(TYPE_SRV, true, 6 + name_len(&host))?;
+            // Priority and weight.
+            self.write(&[0; 4])?;
+            self.write(&HTTP_PORT.to_be_bytes())?;
+            self.write_name(&host)?;
+        }
+
+        if records.text {
+            self.write_name(&instance)?;
+            self.write_record(TYPE_TXT, true, 1)?;
+            // A single empty string for no attributes.
+            self.write(&[0])?;
+        }
+
+        Some(())
+    }
+}
+
+/// Returns the length of the encoded name made of `labels`.
+fn name_len(labels: &[&[u8]]) -> usize {
+    labels.iter().map(|label| 1 + label.len()).sum::<usize>() + 1
+}
+
+/// Reads the name at `offset` in `message`, following compression pointers. Returns its labels and
+/// the offset following the name.
+fn read_name(
+    message: &[u8],
+    mut offset: usize,
+) -> Option<(heapless::Vec<&[u8], MAX_LABELS>, usize)> {
+    let mut labels = heapless::Vec::new();
+    let mut end = None;
+
+    // Pointers only point backwards, anything else is a loop.
+    let mut limit = offset;
+
+    loop {
+        let len = *message.get(offset)? as usize;
+
+        match len & 0xC0 {
+            0x00 if len == 0 => break,
+            0x00 => {
+                labels
+                    .push(message.get(offset + 1..offset + 1 + len)?)
+                    .ok()?;
+                offset += 1 + len;
+            }
+            0xC0 => {
+                let target = ((len & 0x3F) << 8) | *message.get(offset + 1)? as usize;
+
+                if target >= limit {
+                    return None;
+                }
+
+                end.get_or_insert(offset + 2);
+                limit = target;
+                offset = target;
+            }
+            _ => return None,
+        }
+    }
+
+    Some((labels, end.unwrap_or(offset + 1)))
+}
+
+/// Returns whether `labels` spell `name`, ignoring case.
+fn is_name(labels: &[&[u8]], name: &[&[u8]]) -> bool {
+    labels.len() == name.len()
+        && labels
+            .iter()
+            .zip(name)
+            .all(|(label, expected)| label.eq_ignore_ascii_case(expected))
+}
+
+/// Answers mDNS queries for `host_name` on `stack` and announces it whenever the network is up.
+pub async fn serve(stack: Stack<'_>, host_name: &str) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 4];
+    let mut rx_buffer = [0; 1024];
+    let mut tx_meta = [PacketMetadata::EMPTY; 4];
+    let mut tx_buffer = [0; 1024];
+
+    let mut socket = UdpSocket::new(
+        stack,
+        &mut rx_meta,
+        &mut rx_buffer,
+        &mut tx_meta,
+        &mut tx_buffer,
+    );
+
+    if let Err(err) = socket.bind(PORT) {
+        log::error!("failed to bind UDP socket for mDNS: {err:?}");
+        return;
+    }
+
+    let mut query = [0; MAX_MESSAGE_SIZE];
+    let mut response = [0; MAX_MESSAGE_SIZE];
+
+    stack.wait_config_up().await;
+
+    if let Err(err) = stack.join_multicast_group(GROUP) {
+        log::error!("failed to join mDNS multicast group: {err:?}");
+        return;
+    }
+
+    log::info!("answering mDNS queries for {host_name}.local");
+
+    let mut announced = None;
+
+    loop {
+        stack.wait_config_up().await;
+
+        let Some(address) = stack.config_v4().map(|config| config.address.address()) else {
+            continue;
+        };
+
+        // Announce twice a second apart whenever the address changed, e.g. after reconnecting.
+        if announced != Some(address) {
+            for _ in 0..2 {
+                if let Some(len) = announce(host_name, address, &mut response)
+                    && let Err(err) = socket.send_to(&response[..len], (GROUP, PORT)).await
+                {
+                    log::warn!("failed to send mDNS announcement: {err:?}");
+                }
+
+                Timer::after(Duration::from_secs(1)).await;
+            }
+
+            announced = Some(address);
+        }
+
+        let (len, meta) = match socket.recv_from(&mut query).await {
+            Ok(received) => received,
+            Err(err) => {
+                log::warn!("failed to receive mDNS query: {err:?}");
+                continue;
+            }
+        };
+
+        let legacy = meta.endpoint.port != PORT;
+
+        let Some(response_len) = respond(&query[..len], host_name, address, legacy, &mut response)
+        else {
+            continue;
+        };
+
+        let result = if legacy {
+            socket.send_to(&response[..response_len], meta).await
+        } else {
+            socket
+                .send_to(&response[..response_len], (GROUP, PORT))
+                .await
+        };
+
+        if let Err(err) = result {
+            log::warn!("failed to send mDNS response: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    const ADDRESS: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 50);
+
+    const HOST: &[u8] = b"\x09dashboard\x05local\x00";
+    const SERVICE_NAME: &[u8] = b"\x05_http\x04_tcp\x05local\x00";
+    const INSTANCE: &[u8] = b"\x09dashboard\x05_http\x04_tcp\x05local\x00";
+
+    /// Query for the A record of `dashboard.local`, as sent by Avahi.
+    const ADDRESS_QUERY: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        9, b'd', b'a', b's', b'h', b'b', b'o', b'a', b'r', b'd', 5, b'l', b'o', b'c', b'a', b'l',
+        0, //
+        0x00, 0x01, 0x00, 0x01,
+    ];
+
+    /// Query browsing for HTTP servers, as sent by `dns-sd -B _http._tcp`.
+    const BROWSE_QUERY: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        5, b'_', b'h', b't', b't', b'p', 4, b'_', b't', b'c', b'p', 5, b'l', b'o', b'c', b'a',
+        b'l', 0, //
+        0x00, 0x0C, 0x00, 0x01,
+    ];
+
+    const ADDRESS_RECORD: &[u8] = &[
+        0x00, 0x01, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 192, 168, 1, 50,
+    ];
+
+    /// Returns the response to `query` for `dashboard`.
+    fn respond_to(query: &[u8], legacy: bool) -> Option<Vec<u8>> {
+        let mut response = [0; MAX_MESSAGE_SIZE];
+        let len = respond(query, "dashboard", ADDRESS, legacy, &mut response)?;
+
+        Some(response[..len].to_vec())
+    }
+
+    /// Returns the header of a response with `answers` and `additional` records.
+    fn header(answers: u8, additional: u8) -> [u8; 12] {
+        [0, 0, 0x84, 0, 0, 0, 0, answers, 0, 0, 0, additional]
+    }
+
+    fn pointer_record() -> Vec<u8> {
+        let data = [0x00, 0x0C, 0x00, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 28];
+        [SERVICE_NAME, &data, INSTANCE].concat()
+    }
+
+    fn service_record() -> Vec<u8> {
+        let data = [
+            0x00, 0x21, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 23, 0, 0, 0, 0, 0x00, 0x50,
+        ];
+        [INSTANCE, &data, HOST].concat()
+    }
+
+    fn text_record() -> Vec<u8> {
+        let data = [
+            0x00, 0x10, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x01, 0x00,
+        ];
+        [INSTANCE, &data].concat()
+    }
+
+    #[test]
+    fn answers_address_queries() {
+        let expected = [&header(1, 0), HOST, ADDRESS_RECORD].concat();
+
+        assert_eq!(respond_to(ADDRESS_QUERY, false).unwrap(), expected);
+
+        // Asking for a unicast response, for any type and with a differently cased name.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[13] = b'D';
+        query[30] = 0xFF;
+        query[31] = 0x80;
+
+        assert_eq!(respond_to(&query, false).unwrap(), expected);
+    }
+
+    #[test]
+    fn answers_service_queries_with_additional_records() {
+        let expected = [
+            &header(1, 3)[..],
+            &pointer_record(),
+            HOST,
+            ADDRESS_RECORD,
+            &service_record(),
+            &text_record(),
+        ]
+        .concat();
+
+        assert_eq!(respond_to(BROWSE_QUERY, false).unwrap(), expected);
+
+        // Resolving the instance found.
+        let mut query = [&BROWSE_QUERY[..12], INSTANCE, &[0x00, 0x21, 0x00, 0x01]].concat();
+        let expected = [&header(1, 1)[..], &service_record(), HOST, ADDRESS_RECORD].concat();
+
+        assert_eq!(respond_to(&query, false).unwrap(), expected);
+
+        let len = query.len();
+        query[len - 3] = 0x10;
+        let expected = [&header(1, 0)[..], &text_record()].concat();
+
+        assert_eq!(respond_to(&query, false).unwrap(), expected);
+    }
+
+    #[test]
+    fn answers_compressed_questions_together() {
+        // Questions for the services and, pointing to `local` in the first name, for the address.
+        let query = [
+            &[0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0][..],
+            SERVICE_NAME,
+            &[0x00, 0x0C, 0x00, 0x01],
+            b"\x09dashboard\xC0\x17",
+            &[0x00, 0x01, 0x80, 0x01],
+        ]
+        .concat();
+
+        let expected = [
+            &header(2, 2)[..],
+            HOST,
+            ADDRESS_RECORD,
+            &pointer_record(),
+            &service_record(),
+            &text_record(),
+        ]
+        .concat();
+
+        assert_eq!(respond_to(&query, false).unwrap(), expected);
+    }
+
+    #[test]
+    fn answers_legacy_queries_with_question_and_id() {
+        // Query of `dig -p 5353 @224.0.0.251 dashboard.local`, with recursion desired.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[..3].copy_from_slice(&[0xBE, 0xEF, 0x01]);
+
+        let header = [
+            0xBE, 0xEF, 0x84, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let record = [
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x04, 192, 168, 1, 50,
+        ];
+        let expected = [&header, &query[12..], HOST, &record].concat();
+
+        assert_eq!(respond_to(&query, true).unwrap(), expected);
+    }
+
+    #[test]
+    fn ignores_other_names_and_types() {
+        let printer = [
+            &ADDRESS_QUERY[..12],
+            b"\x07printer\x05local\x00",
+            &[0x00, 0x01, 0x00, 0x01],
+        ]
+        .concat();
+        assert_eq!(respond_to(&printer, false), None);
+
+        // AAAA record.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[30] = 28;
+        assert_eq!(respond_to(&query, false), None);
+
+        // Chaos class.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[32] = 3;
+        assert_eq!(respond_to(&query, false), None);
+
+        // Response of another host, and no questions.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[2] = 0x84;
+        assert_eq!(respond_to(&query, false), None);
+
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[5] = 0;
+        assert_eq!(respond_to(&query, false), None);
+    }
+
+    #[test]
+    fn rejects_truncated_queries() {
+        for len in 0..ADDRESS_QUERY.len() {
+            assert_eq!(
+                respond_to(&ADDRESS_QUERY[..len], false),
+                None,
+                "{len} bytes"
+            );
+        }
+
+        // Second question missing.
+        let mut query = ADDRESS_QUERY.to_vec();
+        query[5] = 2;
+        assert_eq!(respond_to(&query, false), None);
+    }
+
+    #[test]
+    fn reads_compressed_names() {
+        let message = b"\x05local\x00\x09dashboard\xC0\x00\x06_other\xC0\x07";
+
+        let (labels, end) = read_name(message, 7).unwrap();
+        assert_eq!(labels, [&b"dashboard"[..], b"local"]);
+        assert_eq!(end, 19);
+
+        let (labels, end) = read_name(message, 19).unwrap();
+        assert_eq!(labels, [&b"_other"[..], b"dashboard", b"local"]);
+        assert_eq!(end, 28);
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        // Pointers to themselves, forwards and in a loop.
+        assert!(read_name(b"\x05local\xC0\x06", 6).is_none());
+        assert!(read_name(b"\xC0\x02\x05local\x00", 0).is_none());
+        assert!(read_name(b"\x01a\xC0\x04\x01b\xC0\x00", 4).is_none());
+
+        // Reserved label type, label past the end and pointer missing its second byte.
+        assert!(read_name(b"\x45local\x00", 0).is_none());
+        assert!(read_name(b"\x06local", 0).is_none());
+        assert!(read_name(b"\x05local\xC0", 0).is_none());
+
+        // More labels than handled.
+        assert!(read_name(b"\x01a\x01a\x01a\x01a\x01a\x01a\x01a\x01a\x00", 0).is_some());
+        assert!(read_name(b"\x01a\x01a\x01a\x01a\x01a\x01a\x01a\x01a\x01a\x00", 0).is_none());
+    }
+
+    #[test]
+    fn announces_all_records() {
+        let mut response = [0; MAX_MESSAGE_SIZE];
+        let len = announce("dashboard", ADDRESS, &mut response).unwrap();
+
+        let expected = [
+            &header(4, 0)[..],
+            HOST,
+            ADDRESS_RECORD,
+            &pointer_record(),
+            &service_record(),
+            &text_record(),
+        ]
+        .concat();
+
+        assert_eq!(response[..len], expected);
+    }
+}
@@ -0,0 +1,215 @@
+//! Setup portal for when the configured Wi-Fi network cannot be joined: an open access point with
+//! DHCP and a DNS server answering every name with its own address, so that phones and laptops
+//! joining it show the settings page right away. Saving the settings restarts the device.
+
+extern crate alloc;
+
+use core::cell::RefCell;
+use core::net::Ipv4Addr;
+
+use alloc::string::String;
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_net::tcp::TcpSocket;
+use embassy_net::{Ipv4Cidr, Stack, StaticConfigV4};
+use embassy_time::{Duration, Timer, with_timeout};
+use embedded_storage::nor_flash::NorFlash;
+use esp_radio::wifi::{AccessPointConfig, ClientConfig, ModeConfig, WifiController};
+
+use crate::config::{Config, form};
+use crate::errors::Error;
+use crate::http::{self, Method};
+use crate::storage::Store;
+use crate::wifi;
+use crate::{dhcp, dns};
+
+/// Name of the open access point.
+pub const SSID: &str = "dashboard-setup";
+
+pub const ADDRESS: Ipv4Addr = Ipv4Addr::new(192, 168, 4, 1);
+
+const PAGE_URL: &str = "http://192.168.4.1/";
+
+const HTTP_PORT: u16 = 80;
+
+/// Time without any request after which the portal gives up and the device restarts to try the
+/// configured network again, e.g. after the router was only down for a while.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+const MAX_SCANNED_NETWORKS: usize = 16;
+const MAX_REQUEST_SIZE: usize = 4096;
+
+/// Returns the network configuration of the access point interface.
+pub fn net_config() -> embassy_net::Config {
+    embassy_net::Config::ipv4_static(StaticConfigV4 {
+        address: Ipv4Cidr::new(ADDRESS, 24),
+        gateway: None,
+        dns_servers: Default::default(),
+    })
+}
+
+/// Scans for networks, opens the access point on `stack` and serves the settings page until new
+/// settings are saved to `store` or nobody used the portal for a while, then restarts.
+pub async fn run<F: NorFlash>(
+    mut controller: WifiController<'_>,
+    stack: Stack<'_>,
+    config: &Config,
+    store: &RefCell<Option<Store<F>>>,
+) -> ! {
+    let networks = scan(&mut controller).await;
+
+    log::info!("starting setup portal, join {SSID} and open {PAGE_URL}");
+
+    controller.stop_async().await.expect("stopping Wi-Fi");
+    controller
+        .set_config(&ModeConfig::AccessPoint(
+            AccessPointConfig::default().with_ssid(SSID.into()),
+        ))
+        .expect("setting Wi-Fi access point config");
+    controller
+        .start_async()
+        .await
+        .expect("starting Wi-Fi access point");
+
+    let servers = join(dhcp::serve(stack, ADDRESS), dns::serve(stack, ADDRESS));
+
+    if let Either::First(_) = select(servers, serve_settings(stack, config, &networks, store)).await
+    {
+        log::error!("setup portal servers stopped");
+    }
+
+    // Give the last response time to reach the browser.
+    Timer::after(Duration::from_secs(1)).await;
+
+    esp_hal::system::software_reset()
+}
+
+/// Returns the names of the networks in range, strongest first.
+async fn scan(
+    controller: &mut WifiController<'_>,
+) -> heapless::Vec<heapless::String<32>, MAX_SCANNED_NETWORKS> {
+    // Scanning needs the station, which is not started without settings.
+    if !controller.is_started().unwrap_or(false) {
+        controller
+            .set_config(&ModeConfig::Client(ClientConfig::default()))
+            .expect("setting Wi-Fi client config");
+        controller.start_async().await.expect("starting Wi-Fi");
+    }
+
+    let access_points = match wifi::scan(controller).await {
+        Ok(access_points) => access_points,
+        Err(err) => {
+            log::warn!("failed to scan for Wi-Fi networks: {err:?}");
+            return heapless::Vec::new();
+        }
+    };
+
+    access_points
+        .into_iter()
+        .filter_map(|access_point| heapless::String::try_from(access_point.ssid.as_str()).ok())
+        .filter(|ssid| !ssid.is_empty())
+        .take(MAX_SCANNED_NETWORKS)
+        .collect()
+}
+
+/// Serves the settings page until settings were saved or no request arrived for a while.
+async fn serve_settings<F: NorFlash>(
+    stack: Stack<'_>,
+    config: &Config,
+    networks: &[heapless::String<32>],
+    store: &RefCell<Option<Store<F>>>,
+) {
+    let mut rx_buffer = [0; 1024];
+    let mut tx_buffer = [0; 1024];
+
+    loop {
+        let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
+        socket.set_timeout(Some(Duration::from_secs(10)));
+
+        match with_timeout(IDLE_TIMEOUT, socket.accept(HTTP_PORT)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                log::warn!("failed to accept connection: {err:?}");
+                continue;
+            }
+            Err(_) => {
+                log::info!("setup portal unused, restarting");
+                return;
+            }
+        }
+
+        let saved = respond(&mut socket, config, networks, store)
+            .await
+            .inspect_err(|err| log::warn!("failed to respond: {err:?}"))
+            .unwrap_or(false);
+
+        socket.close();
+        let _ = socket.flush().await;
+
+        if saved {
+            return;
+        }
+    }
+}
+
+/// Answers a single request and returns whether new settings were saved.
+async fn respond<F: NorFlash>(
+    socket: &mut TcpSocket<'_>,
+    config: &Config,
+    networks: &[heapless::String<32>],
+    store: &RefCell<Option<Store<F>>>,
+) -> Result<bool, Error> {
+    let mut buffer = [0; MAX_REQUEST_SIZE];
+    let request = http::read_request(socket, &mut buffer).await?;
+
+    let error = match (request.method, request.path) {
+        (Method::Get, "/") => None,
+        (Method::Post, "/") => {
+            let saved = core::str::from_utf8(request.body)
+                .map_err(|_| Error::ParseUtf8)
+                .and_then(|body| form::parse(config, body))
+                .and_then(|config| save(&config, store));
+
+            match saved {
+                Ok(()) => {
+                    let page = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"></head><body>\
+                        <p>Saved. The dashboard restarts and joins the strongest of its Wi-Fi \
+                        networks.</p></body></html>";
+
+                    http::write_response(socket, "200 OK", "text/html", page.as_bytes()).await?;
+
+                    return Ok(true);
+                }
+                Err(err) => Some(err),
+            }
+        }
+        // Captive portal checks of the various operating systems, redirected to the form.
+        _ => {
+            http::write_redirect(socket, PAGE_URL).await?;
+            return Ok(false);
+        }
+    };
+
+    let mut page = String::new();
+
+    form::render(
+        &mut page,
+        "/",
+        config,
+        networks.iter().map(|network| network.as_str()),
+        error.as_ref(),
+    )
+    .map_err(|_| Error::Http("failed to render settings page"))?;
+
+    http::write_response(socket, "200 OK", "text/html", page.as_bytes()).await?;
+
+    Ok(false)
+}
+
+fn save<F: NorFlash>(config: &Config, store: &RefCell<Option<Store<F>>>) -> Result<(), Error> {
+    store
+        .borrow_mut()
+        .as_mut()
+        .ok_or(Error::Storage("no storage"))
+        .and_then(|store| config.save(store))
+}
@@ -0,0 +1,76 @@
+//! Fetching each data source in its own interval, retrying failed fetches with exponential backoff.
+
+use embassy_time::{Duration, Instant};
+
+use crate::status::Source;
+
+/// Delay before retrying a failed fetch, doubled with every further failure up to the interval of
+/// the source.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Copy, Clone, Debug)]
+struct Schedule {
+    interval: Duration,
+    next: Instant,
+    failures: u32,
+}
+
+/// When each data source is due to be fetched next.
+#[derive(Debug)]
+pub struct Scheduler {
+    schedules: [Option<Schedule>; Source::ALL.len()],
+}
+
+impl Scheduler {
+    /// Creates a scheduler fetching each source every `interval(source)` or never if that is
+    /// `None`. All sources are due immediately.
+    pub fn new(interval: impl Fn(Source) -> Option<Duration>) -> Self {
+        Self {
+            schedules: Source::ALL.map(|source| {
+                interval(source).map(|interval| Schedule {
+                    interval,
+                    next: Instant::from_ticks(0),
+                    failures: 0,
+                })
+            }),
+        }
+    }
+
+    pub fn is_due(&self, source: Source, now: Instant) -> bool {
+        self.schedules[source as usize].is_some_and(|schedule| schedule.next <= now)
+    }
+
+    /// Schedules the next fetch of `source` one interval after `now`.
+    pub fn succeeded(&mut self, source: Source, now: Instant) {
+        if let Some(schedule) = &mut self.schedules[source as usize] {
+            schedule.failures = 0;
+            schedule.next = now + schedule.interval;
+        }
+    }
+
+    /// Schedules a retry of `source` after a delay growing with the number of consecutive failures.
+    pub fn failed(&mut self, source: Source, now: Instant) {
+        if let Some(schedule) = &mut self.schedules[source as usize] {
+            let backoff = RETRY_DELAY * (1 << schedule.failures.min(16));
+
+            schedule.failures += 1;
+            schedule.next = now + backoff.min(schedule.interval);
+        }
+    }
+
+    /// Makes all sources due at `now`, e.g. when a refresh was requested.
+    pub fn fetch_all(&mut self, now: Instant) {
+        for schedule in self.schedules.iter_mut().flatten() {
+            schedule.next = now;
+        }
+    }
+
+    /// Returns when the next source is due or `None` if no source is fetched at all.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.schedules
+            .iter()
+            .flatten()
+            .map(|schedule| schedule.next)
+            .min()
+    }
+}
@@ -0,0 +1,76 @@
+//! Encoding of the frame buffer as 1-bit BMP image in the orientation the dashboard is drawn in,
+//! row by row so that it can be streamed without a copy of the whole frame.
+
+use crate::layout;
+
+/// Width and height of the display in the panel's native, landscape orientation, in which the
+/// frame buffer is laid out.
+const NATIVE_WIDTH: usize = layout::HEIGHT as usize;
+const NATIVE_ROW_BYTES: usize = NATIVE_WIDTH / 8;
+
+/// Bytes of each image row, already a multiple of four as BMP requires.
+pub const ROW_BYTES: usize = layout::WIDTH as usize / 8;
+
+pub const ROWS: usize = layout::HEIGHT as usize;
+
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+const PALETTE_SIZE: usize = 2 * 4;
+
+pub const HEADER_SIZE: usize = FILE_HEADER_SIZE + INFO_HEADER_SIZE + PALETTE_SIZE;
+
+/// Size of the whole image in bytes.
+pub const SIZE: usize = HEADER_SIZE + ROWS * ROW_BYTES;
+
+/// Returns the file header, info header and palette of the image.
+pub fn header() -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    let mut len = 0;
+    let mut write = |bytes: &[u8]| {
+        header[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+    };
+
+    write(b"BM");
+    write(&(SIZE as u32).to_le_bytes());
+    write(&[0; 4]);
+    write(&(HEADER_SIZE as u32).to_le_bytes());
+
+    write(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+    write(&(layout::WIDTH as i32).to_le_bytes());
+    // Negative for rows from top to bottom.
+    write(&(-(ROWS as i32)).to_le_bytes());
+    // Planes, bits per pixel and no compression.
+    write(&1u16.to_le_bytes());
+    write(&1u16.to_le_bytes());
+    write(&0u32.to_le_bytes());
+    write(&((ROWS * ROW_BYTES) as u32).to_le_bytes());
+    // Resolution of about 125 dpi in pixels per meter, and the number of palette colors.
+    write(&4921u32.to_le_bytes());
+    write(&4921u32.to_le_bytes());
+    write(&2u32.to_le_bytes());
+    write(&2u32.to_le_bytes());
+
+    // Bits are set for white pixels in the frame buffer, as blue, green, red and reserved.
+    write(&[0x00, 0x00, 0x00, 0x00]);
+    write(&[0xFF, 0xFF, 0xFF, 0x00]);
+
+    header
+}
+
+/// Writes image row `y` of the native frame `buffer` into `row`.
+pub fn row(buffer: &[u8], y: usize, row: &mut [u8; ROW_BYTES]) {
+    // The rotated y axis runs from right to left along the native x axis, the rotated x axis
+    // along the native y axis.
+    let native_x = NATIVE_WIDTH - 1 - y;
+    let native_byte = native_x / 8;
+    let native_bit = 0x80 >> (native_x % 8);
+
+    row.fill(0);
+
+    for x in 0..layout::WIDTH as usize {
+        if buffer[x * NATIVE_ROW_BYTES + native_byte] & native_bit != 0 {
+            row[x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+}
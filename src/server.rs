@@ -0,0 +1,294 @@
+//! HTTP server on the local network to check the status of the dashboard, trigger a refresh and
+//! edit its settings without reflashing.
+//!
+//! Routes:
+//! - `GET /`: links to the other routes and a refresh button
+//! - `GET /status`: outcome of the latest fetch of each source as JSON
+//! - `POST /refresh`: fetch all sources now
+//! - `GET /screenshot.bmp`: the frame last shown on the display
+//! - `GET /config`, `POST /config`: settings form, saving restarts the device
+//!
+//! Requests for other hosts or from pages of other sites are rejected. Refreshing and the settings
+//! require the admin password, if set.
+
+extern crate alloc;
+
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use core::net::Ipv4Addr;
+
+use alloc::string::String;
+use embassy_net::Stack;
+use embassy_net::tcp::TcpSocket;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer};
+use embedded_io_async::Write as _;
+use embedded_storage::nor_flash::NorFlash;
+use epd_waveshare::epd7in5_v2::Display7in5;
+
+use crate::clock::Clock;
+use crate::config::{Config, form};
+use crate::errors::Error;
+use crate::http::{self, Method};
+use crate::json::Quoted;
+use crate::screenshot;
+use crate::status::{Refresh, Source};
+use crate::storage::Store;
+use crate::wifi::SignalStrength;
+
+const PORT: u16 = 80;
+
+const MAX_REQUEST_SIZE: usize = 4096;
+
+/// Realm of the admin password, shown by browsers when asking for it.
+const REALM: &str = "dashboard";
+
+/// Number of image rows encoded and sent at once.
+const SCREENSHOT_CHUNK_ROWS: usize = 8;
+
+const INDEX_PAGE: &str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+    <meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+    <title>Dashboard</title></head><body><h1>Dashboard</h1>\
+    <p><a href=\"/status\">Status</a></p><p><a href=\"/screenshot.bmp\">Screenshot</a></p>\
+    <p><a href=\"/config\">Settings</a></p>\
+    <form method=\"post\" action=\"/refresh\"><button>Refresh now</button></form></body></html>";
+
+/// Everything the server shows or changes.
+pub struct State<'a, F> {
+    pub config: &'a Config,
+    pub store: &'a RefCell<Option<Store<F>>>,
+    pub refresh: &'a RefCell<Refresh>,
+    /// Locked while a frame is drawn and shown.
+    pub display: &'a Mutex<NoopRawMutex, Display7in5>,
+    pub clock: Clock,
+    pub signal_strength: SignalStrength,
+    /// Signaled to fetch all sources right away.
+    pub refresh_requested: &'a Signal<NoopRawMutex, ()>,
+}
+
+/// Serves requests on `stack`, one at a time, and restarts once new settings were saved.
+pub async fn run<F: NorFlash>(stack: Stack<'_>, state: State<'_, F>) -> ! {
+    let mut rx_buffer = [0; 1024];
+    let mut tx_buffer = [0; 1024];
+
+    loop {
+        stack.wait_config_up().await;
+
+        let mut socket = TcpSocket::new(stack, &mut rx_buffer, &mut tx_buffer);
+        socket.set_timeout(Some(Duration::from_secs(10)));
+
+        if let Err(err) = socket.accept(PORT).await {
+            log::warn!("failed to accept connection: {err:?}");
+            continue;
+        }
+
+        let address = stack.config_v4().map(|config| config.address.address());
+
+        let saved = respond(&mut socket, &state, address)
+            .await
+            .inspect_err(|err| log::warn!("failed to respond: {err:?}"))
+            .unwrap_or(false);
+
+        socket.close();
+        let _ = socket.flush().await;
+
+        if saved {
+            log::info!("settings saved, restarting");
+
+            // Give the response time to reach the browser.
+            Timer::after(Duration::from_secs(1)).await;
+
+            esp_hal::system::software_reset();
+        }
+    }
+}
+
+/// Answers a single request and returns whether new settings were saved.
+async fn respond<F: NorFlash>(
+    socket: &mut TcpSocket<'_>,
+    state: &State<'_, F>,
+    address: Option<Ipv4Addr>,
+) -> Result<bool, Error> {
+    let mut buffer = [0; MAX_REQUEST_SIZE];
+    let request = http::read_request(socket, &mut buffer).await?;
+
+    if !request.is_same_origin(&state.config.host_name, address) {
+        log::warn!("rejected request from another origin");
+        http::write_response(socket, "403 Forbidden", "text/plain", b"forbidden").await?;
+        return Ok(false);
+    }
+
+    if matches!(request.path, "/refresh" | "/config")
+        && !request.is_authorized(&state.config.admin_password)
+    {
+        http::write_unauthorized(socket, REALM).await?;
+        return Ok(false);
+    }
+
+    match (request.method, request.path) {
+        (Method::Get, "/") => {
+            http::write_response(socket, "200 OK", "text/html", INDEX_PAGE.as_bytes()).await?;
+        }
+        (Method::Get, "/status") => {
+            let mut status = String::new();
+
+            write_status(&mut status, state).map_err(|_| Error::Http("failed to render status"))?;
+
+            http::write_response(socket, "200 OK", "application/json", status.as_bytes()).await?;
+        }
+        (Method::Get, "/screenshot.bmp") => {
+            write_screenshot(socket, state.display).await?;
+        }
+        (Method::Post, "/refresh") => {
+            log::info!("refresh requested");
+            state.refresh_requested.signal(());
+
+            http::write_redirect(socket, "/").await?;
+        }
+        (Method::Get, "/config") => {
+            write_config_page(socket, state.config, None).await?;
+        }
+        (Method::Post, "/config") => {
+            let saved = core::str::from_utf8(request.body)
+                .map_err(|_| Error::ParseUtf8)
+                .and_then(|body| form::parse(state.config, body))
+                .and_then(|config| save(&config, state.store));
+
+            if let Err(err) = saved {
+                write_config_page(socket, state.config, Some(&err)).await?;
+                return Ok(false);
+            }
+
+            let page = "<!DOCTYPE html><html><head><meta charset=\"utf-8\"></head><body>\
+                <p>Saved. The dashboard restarts.</p></body></html>";
+
+            http::write_response(socket, "200 OK", "text/html", page.as_bytes()).await?;
+
+            return Ok(true);
+        }
+        _ => {
+            http::write_response(socket, "404 Not Found", "text/plain", b"not found").await?;
+        }
+    }
+
+    Ok(false)
+}
+
+async fn write_config_page(
+    socket: &mut TcpSocket<'_>,
+    config: &Config,
+    error: Option<&Error>,
+) -> Result<(), Error> {
+    let mut page = String::new();
+
+    form::render(&mut page, "/config", config, core::iter::empty(), error)
+        .map_err(|_| Error::Http("failed to render settings page"))?;
+
+    let status = if error.is_some() {
+        "400 Bad Request"
+    } else {
+        "200 OK"
+    };
+
+    http::write_response(socket, status, "text/html", page.as_bytes()).await
+}
+
+/// Streams the frame buffer as BMP image. The display stays locked until the whole image is sent
+/// so that it shows a single frame.
+async fn write_screenshot(
+    socket: &mut TcpSocket<'_>,
+    display: &Mutex<NoopRawMutex, Display7in5>,
+) -> Result<(), Error> {
+    let display = display.lock().await;
+
+    http::write_head(socket, "200 OK", "image/bmp", screenshot::SIZE).await?;
+
+    socket
+        .write_all(&screenshot::header())
+        .await
+        .map_err(|_| Error::Http("failed to write response"))?;
+
+    let mut rows = [[0; screenshot::ROW_BYTES]; SCREENSHOT_CHUNK_ROWS];
+
+    for first in (0..screenshot::ROWS).step_by(SCREENSHOT_CHUNK_ROWS) {
+        for (y, row) in (first..).zip(&mut rows) {
+            screenshot::row(display.buffer(), y, row);
+        }
+
+        socket
+            .write_all(rows.as_flattened())
+            .await
+            .map_err(|_| Error::Http("failed to write response"))?;
+    }
+
+    socket
+        .flush()
+        .await
+        .map_err(|_| Error::Http("failed to write response"))
+}
+
+/// Writes the status as JSON like
+/// `{"time":"…","rssi":-67,"since_ntp_sync":300,"sources":{"alerts":{"ok":true,…},…},…}`, with
+/// `null` for unknown values and `"ok":null` for sources not fetched yet.
+fn write_status<F>(out: &mut impl Write, state: &State<'_, F>) -> fmt::Result {
+    let refresh = state.refresh.borrow();
+
+    write!(out, "{{\"time\":")?;
+    write_zoned(out, Some(&state.clock.now()))?;
+
+    write!(out, ",\"rssi\":")?;
+    write_optional(out, state.signal_strength.rssi())?;
+
+    write!(out, ",\"since_ntp_sync\":")?;
+    write_optional(out, state.clock.since_sync().map(|since| since.as_secs()))?;
+
+    write!(out, ",\"sources\":{{")?;
+
+    for (index, source) in Source::ALL.into_iter().enumerate() {
+        if index > 0 {
+            write!(out, ",")?;
+        }
+
+        write!(out, "{}:{{\"ok\":", Quoted(source.name()))?;
+        write_optional(out, refresh.result(source))?;
+        write!(out, ",\"fetched\":")?;
+        write_zoned(out, refresh.fetched(source))?;
+        write!(out, "}}")?;
+    }
+
+    write!(out, "}},\"last_error\":")?;
+
+    match refresh.last_error() {
+        Some(error) => {
+            let message: heapless::String<96> = heapless::format!("{error:?}").unwrap_or_default();
+            write!(out, "{}", Quoted(&message))?;
+        }
+        None => write!(out, "null")?,
+    }
+
+    write!(out, "}}")
+}
+
+fn write_optional(out: &mut impl Write, value: Option<impl fmt::Display>) -> fmt::Result {
+    match value {
+        Some(value) => write!(out, "{value}"),
+        None => write!(out, "null"),
+    }
+}
+
+fn write_zoned(out: &mut impl Write, zoned: Option<&jiff::Zoned>) -> fmt::Result {
+    match zoned {
+        Some(zoned) => write!(out, "\"{}\"", zoned.timestamp()),
+        None => write!(out, "null"),
+    }
+}
+
+fn save<F: NorFlash>(config: &Config, store: &RefCell<Option<Store<F>>>) -> Result<(), Error> {
+    store
+        .borrow_mut()
+        .as_mut()
+        .ok_or(Error::Storage("no storage"))
+        .and_then(|store| config.save(store))
+}
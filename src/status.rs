@@ -0,0 +1,85 @@
+use crate::errors::Error;
+
+/// Data sources fetched on every refresh.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    HourlyWeather,
+    Alerts,
+    DailyWeather,
+    AirQuality,
+    Events,
+    Todos,
+}
+
+impl Source {
+    pub const ALL: [Source; 6] = [
+        Source::HourlyWeather,
+        Source::Alerts,
+        Source::DailyWeather,
+        Source::AirQuality,
+        Source::Events,
+        Source::Todos,
+    ];
+
+    /// Identifier of the source in the status report.
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::HourlyWeather => "hourly_weather",
+            Source::Alerts => "alerts",
+            Source::DailyWeather => "daily_weather",
+            Source::AirQuality => "air_quality",
+            Source::Events => "events",
+            Source::Todos => "todos",
+        }
+    }
+}
+
+/// Outcome of the latest fetch of each data source.
+#[derive(Debug, Default)]
+pub struct Refresh {
+    /// `Some(true)` for sources fetched successfully, `None` for sources not fetched at all.
+    results: [Option<bool>; Source::ALL.len()],
+    /// Time of the last successful fetch of each source.
+    fetched: [Option<jiff::Zoned>; Source::ALL.len()],
+    last_error: Option<Error>,
+}
+
+impl Refresh {
+    pub fn succeeded(&mut self, source: Source, fetched: jiff::Zoned) {
+        self.results[source as usize] = Some(true);
+        self.fetched[source as usize] = Some(fetched);
+    }
+
+    pub fn failed(&mut self, source: Source, error: Error) {
+        self.results[source as usize] = Some(false);
+        self.last_error = Some(error);
+    }
+
+    /// Returns `Some(true)` if `source` was fetched successfully, `Some(false)` if fetching it
+    /// failed and `None` if it was not fetched.
+    pub fn result(&self, source: Source) -> Option<bool> {
+        self.results[source as usize]
+    }
+
+    /// Returns when `source` was last fetched successfully.
+    pub fn fetched(&self, source: Source) -> Option<&jiff::Zoned> {
+        self.fetched[source as usize].as_ref()
+    }
+
+    /// Returns the sources that failed to refresh.
+    pub fn failed_sources(&self) -> impl Iterator<Item = Source> + '_ {
+        Source::ALL
+            .into_iter()
+            .filter(|source| self.result(*source) == Some(false))
+    }
+
+    /// Returns `true` if at least one source was fetched and none of them succeeded.
+    pub fn all_failed(&self) -> bool {
+        self.results.iter().any(Option::is_some) && !self.results.contains(&Some(true))
+    }
+
+    /// Returns the error of the source that failed last.
+    pub fn last_error(&self) -> Option<&Error> {
+        self.last_error.as_ref()
+    }
+}
@@ -0,0 +1,717 @@
+//! Key-value store in a flash partition, written as a log of records to spread wear over all of
+//! its sectors.
+//!
+//! Each sector starts with a header holding a sequence number, followed by records made of a
+//! header with key, length and CRC and the value padded to [`ALIGN`] bytes. The latest valid record
+//! of a key wins, so a record torn by a reset simply falls back to the previous one. One sector is
+//! always kept erased: when the active sector is full, the erased one becomes active, the records
+//! still current in the oldest sector are copied into it and the oldest sector is erased.
+//!
+//! The store only relies on [`NorFlash`], so the format works the same on flash kept in RAM.
+
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::errors::Error;
+
+const SECTOR_MAGIC: u32 = 0x4853_4144;
+const SECTOR_HEADER_SIZE: u32 = 8;
+const RECORD_HEADER_SIZE: u32 = 8;
+
+/// Alignment of all reads and writes, must be a multiple of the read and write size of the flash.
+const ALIGN: u32 = 4;
+
+const ERASED: u8 = 0xFF;
+
+/// Size of the buffer used to check and copy records.
+const CHUNK_SIZE: usize = 64;
+
+#[derive(Copy, Clone, Debug)]
+struct Record {
+    /// Offset of the record header in the flash.
+    offset: u32,
+    key: u8,
+    len: u16,
+    crc: u32,
+}
+
+impl Record {
+    fn size(&self) -> u32 {
+        RECORD_HEADER_SIZE + align(self.len as u32)
+    }
+
+    fn value_offset(&self) -> u32 {
+        self.offset + RECORD_HEADER_SIZE
+    }
+}
+
+pub struct Store<F> {
+    flash: F,
+    sector_size: u32,
+    sectors: u32,
+    active: u32,
+    sequence: u32,
+    /// Offset of the first free byte in the active sector.
+    position: u32,
+}
+
+impl<F: NorFlash> Store<F> {
+    /// Opens the store in `flash`, formatting it if it holds no store yet.
+    pub fn open(flash: F) -> Result<Self, Error> {
+        if !ALIGN.is_multiple_of(F::READ_SIZE as u32) || !ALIGN.is_multiple_of(F::WRITE_SIZE as u32)
+        {
+            return Err(Error::Storage("unsupported flash alignment"));
+        }
+
+        let sector_size = F::ERASE_SIZE as u32;
+        let sectors = flash.capacity() as u32 / sector_size;
+
+        if sectors < 2 {
+            return Err(Error::Storage("storage needs at least two sectors"));
+        }
+
+        let mut store = Self {
+            flash,
+            sector_size,
+            sectors,
+            active: 0,
+            sequence: 0,
+            position: 0,
+        };
+
+        let mut active = None;
+
+        for sector in 0..sectors {
+            if let Some(sequence) = store.sector_sequence(sector)?
+                && active.is_none_or(|(_, latest)| sequence > latest)
+            {
+                active = Some((sector, sequence));
+            }
+        }
+
+        let Some((sector, sequence)) = active else {
+            log::info!("formatting storage");
+            store.format()?;
+            return Ok(store);
+        };
+
+        store.active = sector;
+        store.sequence = sequence;
+        store.position = store.end_of_records(sector)?;
+
+        // Finish rotating sectors if that was interrupted by a reset.
+        let spare = store.next_sector(sector);
+
+        if !store.is_erased(store.sector_start(spare), sector_size)? {
+            store.collect(spare)?;
+        }
+
+        Ok(store)
+    }
+
+    /// Reads the value of `key` into `buffer` and returns it or `None` if there is none.
+    pub fn read<'b>(&mut self, key: u8, buffer: &'b mut [u8]) -> Result<Option<&'b [u8]>, Error> {
+        let Some(record) = self.find_latest(key)? else {
+            return Ok(None);
+        };
+
+        let value = buffer
+            .get_mut(..record.len as usize)
+            .ok_or(Error::Storage("buffer too small for stored value"))?;
+
+        let aligned = value.len() - value.len() % ALIGN as usize;
+        let (head, tail) = value.split_at_mut(aligned);
+
+        if !head.is_empty() {
+            self.read_flash(record.value_offset(), head)?;
+        }
+
+        if !tail.is_empty() {
+            let mut chunk = [0; ALIGN as usize];
+            self.read_flash(record.value_offset() + aligned as u32, &mut chunk)?;
+            tail.copy_from_slice(&chunk[..tail.len()]);
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Writes `value` for `key`, which must not be `0xFF`. Writing the stored value again does not
+    /// touch the flash.
+    pub fn write(&mut self, key: u8, value: &[u8]) -> Result<(), Error> {
+        if key == ERASED {
+            return Err(Error::Storage("invalid key"));
+        }
+
+        let max_len = self.sector_size - SECTOR_HEADER_SIZE - RECORD_HEADER_SIZE;
+
+        let len = u16::try_from(value.len())
+            .ok()
+            .filter(|len| align(*len as u32) <= max_len)
+            .ok_or(Error::Storage("value too large"))?;
+
+        let crc = crc32(crc32(crc32(!0, &[key]), &len.to_le_bytes()), value);
+
+        if let Some(latest) = self.find_latest(key)?
+            && latest.len == len
+            && latest.crc == !crc
+        {
+            return Ok(());
+        }
+
+        let mut record = Record {
+            offset: self.position,
+            key,
+            len,
+            crc: !crc,
+        };
+
+        if record.offset + record.size() > self.sector_end(self.active) {
+            self.rotate()?;
+            record.offset = self.position;
+
+            if record.offset + record.size() > self.sector_end(self.active) {
+                return Err(Error::Storage("storage full"));
+            }
+        }
+
+        let mut header = [0; RECORD_HEADER_SIZE as usize];
+        header[0] = key;
+        header[2..4].copy_from_slice(&len.to_le_bytes());
+        header[4..8].copy_from_slice(&record.crc.to_le_bytes());
+
+        self.write_flash(record.offset, &header)?;
+
+        let aligned = value.len() - value.len() % ALIGN as usize;
+        let (head, tail) = value.split_at(aligned);
+
+        if !head.is_empty() {
+            self.write_flash(record.value_offset(), head)?;
+        }
+
+        if !tail.is_empty() {
+            let mut chunk = [ERASED; ALIGN as usize];
+            chunk[..tail.len()].copy_from_slice(tail);
+            self.write_flash(record.value_offset() + aligned as u32, &chunk)?;
+        }
+
+        self.position += record.size();
+
+        Ok(())
+    }
+
+    /// Erases all sectors and starts over with the first one.
+    fn format(&mut self) -> Result<(), Error> {
+        self.flash
+            .erase(0, self.sectors * self.sector_size)
+            .map_err(|_| Error::Storage("failed to erase flash"))?;
+
+        self.sequence = 0;
+        self.activate(0)
+    }
+
+    /// Makes the erased `sector` the active one.
+    fn activate(&mut self, sector: u32) -> Result<(), Error> {
+        self.sequence += 1;
+
+        let mut header = [0; SECTOR_HEADER_SIZE as usize];
+        header[..4].copy_from_slice(&SECTOR_MAGIC.to_le_bytes());
+        header[4..].copy_from_slice(&self.sequence.to_le_bytes());
+
+        self.write_flash(self.sector_start(sector), &header)?;
+
+        self.active = sector;
+        self.position = self.sector_start(sector) + SECTOR_HEADER_SIZE;
+
+        Ok(())
+    }
+
+    /// Continues in the erased spare sector and frees the oldest one.
+    fn rotate(&mut self) -> Result<(), Error> {
+        self.activate(self.next_sector(self.active))?;
+        self.collect(self.next_sector(self.active))
+    }
+
+    /// Copies the records of `sector` that are still current into the active sector and erases it.
+    fn collect(&mut self, sector: u32) -> Result<(), Error> {
+        if self.sector_sequence(sector)?.is_some() {
+            let mut offset = self.sector_start(sector) + SECTOR_HEADER_SIZE;
+
+            while let Some(record) = self.record_at(sector, offset)? {
+                if self
+                    .find_latest(record.key)?
+                    .is_some_and(|latest| latest.offset == record.offset)
+                {
+                    self.copy(&record)?;
+                }
+
+                offset += record.size();
+            }
+        }
+
+        let start = self.sector_start(sector);
+
+        self.flash
+            .erase(start, start + self.sector_size)
+            .map_err(|_| Error::Storage("failed to erase flash"))
+    }
+
+    fn copy(&mut self, record: &Record) -> Result<(), Error> {
+        if self.position + record.size() > self.sector_end(self.active) {
+            return Err(Error::Storage("storage full"));
+        }
+
+        let mut chunk = [0; CHUNK_SIZE];
+        let mut copied = 0;
+
+        while copied < record.size() {
+            let len = (record.size() - copied).min(CHUNK_SIZE as u32);
+            let chunk = &mut chunk[..len as usize];
+
+            self.read_flash(record.offset + copied, chunk)?;
+            self.write_flash(self.position + copied, chunk)?;
+
+            copied += len;
+        }
+
+        self.position += record.size();
+
+        Ok(())
+    }
+
+    /// Returns the latest valid record of `key`, searching from the oldest to the active sector.
+    fn find_latest(&mut self, key: u8) -> Result<Option<Record>, Error> {
+        let mut latest = None;
+        let (active, sectors) = (self.active, self.sectors);
+
+        for sector in (1..=sectors).map(|i| (active + i) % sectors) {
+            if self.sector_sequence(sector)?.is_none() {
+                continue;
+            }
+
+            let mut offset = self.sector_start(sector) + SECTOR_HEADER_SIZE;
+
+            while let Some(record) = self.record_at(sector, offset)? {
+                if record.key == key && self.is_valid(&record)? {
+                    latest = Some(record);
+                }
+
+                offset += record.size();
+            }
+        }
+
+        Ok(latest)
+    }
+
+    /// Returns the record at `offset` in `sector` or `None` at the end of its records.
+    fn record_at(&mut self, sector: u32, offset: u32) -> Result<Option<Record>, Error> {
+        let end = self.sector_end(sector);
+
+        if offset + RECORD_HEADER_SIZE > end {
+            return Ok(None);
+        }
+
+        let mut header = [0; RECORD_HEADER_SIZE as usize];
+        self.read_flash(offset, &mut header)?;
+
+        if header.iter().all(|byte| *byte == ERASED) {
+            return Ok(None);
+        }
+
+        let record = Record {
+            offset,
+            key: header[0],
+            len: u16::from_le_bytes([header[2], header[3]]),
+            crc: u32::from_le_bytes([header[4], header[5], header[6], header[7]]),
+        };
+
+        // A header torn by a reset, nothing after it can be trusted.
+        if offset + record.size() > end {
+            return Ok(None);
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Returns the offset after the last record of `sector`, which is the end of the sector if
+    /// writing a record was interrupted in a way that leaves its size unknown.
+    fn end_of_records(&mut self, sector: u32) -> Result<u32, Error> {
+        let end = self.sector_end(sector);
+        let mut offset = self.sector_start(sector) + SECTOR_HEADER_SIZE;
+
+        while let Some(record) = self.record_at(sector, offset)? {
+            offset += record.size();
+        }
+
+        if self.is_erased(offset, end - offset)? {
+            Ok(offset)
+        } else {
+            Ok(end)
+        }
+    }
+
+    fn is_valid(&mut self, record: &Record) -> Result<bool, Error> {
+        let mut crc = crc32(crc32(!0, &[record.key]), &record.len.to_le_bytes());
+        let mut chunk = [0; CHUNK_SIZE];
+        let mut checked = 0;
+
+        while checked < record.len as u32 {
+            let len = align(record.len as u32 - checked).min(CHUNK_SIZE as u32);
+            let chunk = &mut chunk[..len as usize];
+
+            self.read_flash(record.value_offset() + checked, chunk)?;
+
+            let value_len = (record.len as u32 - checked).min(len);
+            crc = crc32(crc, &chunk[..value_len as usize]);
+            checked += len;
+        }
+
+        Ok(!crc == record.crc)
+    }
+
+    /// Returns the sequence number of `sector` or `None` if it holds no records.
+    fn sector_sequence(&mut self, sector: u32) -> Result<Option<u32>, Error> {
+        let mut header = [0; SECTOR_HEADER_SIZE as usize];
+        self.read_flash(self.sector_start(sector), &mut header)?;
+
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let sequence = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        Ok((magic == SECTOR_MAGIC).then_some(sequence))
+    }
+
+    fn is_erased(&mut self, offset: u32, len: u32) -> Result<bool, Error> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let mut checked = 0;
+
+        while checked < len {
+            let chunk_len = (len - checked).min(CHUNK_SIZE as u32);
+            let chunk = &mut chunk[..chunk_len as usize];
+
+            self.read_flash(offset + checked, chunk)?;
+
+            if chunk.iter().any(|byte| *byte != ERASED) {
+                return Ok(false);
+            }
+
+            checked += chunk_len;
+        }
+
+        Ok(true)
+    }
+
+    fn read_flash(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        self.flash
+            .read(offset, bytes)
+            .map_err(|_| Error::Storage("failed to read flash"))
+    }
+
+    fn write_flash(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        self.flash
+            .write(offset, bytes)
+            .map_err(|_| Error::Storage("failed to write flash"))
+    }
+
+    fn next_sector(&self, sector: u32) -> u32 {
+        (sector + 1) % self.sectors
+    }
+
+    fn sector_start(&self, sector: u32) -> u32 {
+        sector * self.sector_size
+    }
+
+    fn sector_end(&self, sector: u32) -> u32 {
+        self.sector_start(sector) + self.sector_size
+    }
+}
+
+/// Rounds `len` up to a multiple of [`ALIGN`].
+fn align(len: u32) -> u32 {
+    len.div_ceil(ALIGN) * ALIGN
+}
+
+/// Updates the CRC-32 (IEEE) `crc` with `bytes`, starting with `!0` and inverting the result.
+fn crc32(mut crc: u32, bytes: &[u8]) -> u32 {
+    for byte in bytes {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_storage::nor_flash::{ErrorType, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+
+    use super::*;
+
+    const SECTOR_SIZE: usize = 256;
+
+    /// Flash kept in RAM that, like NOR flash, only clears bits when writing. Once `budget` bytes
+    /// were written, the power is cut: the write in progress is torn and all further ones fail.
+    struct RamFlash {
+        bytes: Vec<u8>,
+        budget: Option<usize>,
+    }
+
+    impl RamFlash {
+        fn new(sectors: usize) -> Self {
+            Self {
+                bytes: vec![ERASED; sectors * SECTOR_SIZE],
+                budget: None,
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct PowerLoss;
+
+    impl NorFlashError for PowerLoss {
+        fn kind(&self) -> NorFlashErrorKind {
+            NorFlashErrorKind::Other
+        }
+    }
+
+    impl ErrorType for RamFlash {
+        type Error = PowerLoss;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 4;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), PowerLoss> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.bytes[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.bytes.len()
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = SECTOR_SIZE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), PowerLoss> {
+            if self.budget == Some(0) {
+                return Err(PowerLoss);
+            }
+
+            self.bytes[from as usize..to as usize].fill(ERASED);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), PowerLoss> {
+            assert!(offset.is_multiple_of(4) && bytes.len().is_multiple_of(4));
+
+            let len = self
+                .budget
+                .map_or(bytes.len(), |budget| budget.min(bytes.len()));
+            let offset = offset as usize;
+
+            for (stored, byte) in self.bytes[offset..offset + len].iter_mut().zip(bytes) {
+                *stored &= byte;
+            }
+
+            match &mut self.budget {
+                Some(budget) if *budget < bytes.len() => {
+                    *budget = 0;
+                    Err(PowerLoss)
+                }
+                Some(budget) => {
+                    *budget -= len;
+                    Ok(())
+                }
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn read(store: &mut Store<&mut RamFlash>, key: u8) -> Option<Vec<u8>> {
+        let mut buffer = [0; SECTOR_SIZE];
+        store
+            .read(key, &mut buffer)
+            .unwrap()
+            .map(|value| value.to_vec())
+    }
+
+    #[test]
+    fn writes_and_reads() {
+        let mut flash = RamFlash::new(2);
+        let mut store = Store::open(&mut flash).unwrap();
+
+        assert_eq!(read(&mut store, 1), None);
+
+        store.write(1, b"one").unwrap();
+        store.write(2, b"").unwrap();
+        store.write(3, b"three333").unwrap();
+
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"one"[..]));
+        assert_eq!(read(&mut store, 2).as_deref(), Some(&b""[..]));
+        assert_eq!(read(&mut store, 3).as_deref(), Some(&b"three333"[..]));
+
+        let mut store = Store::open(&mut flash).unwrap();
+
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"one"[..]));
+        assert_eq!(read(&mut store, 3).as_deref(), Some(&b"three333"[..]));
+    }
+
+    #[test]
+    fn rejects_invalid_writes() {
+        let mut flash = RamFlash::new(2);
+        let mut store = Store::open(&mut flash).unwrap();
+
+        assert!(store.write(ERASED, b"x").is_err());
+        assert!(store.write(1, &[0; SECTOR_SIZE]).is_err());
+
+        store.write(1, b"long value").unwrap();
+        assert!(store.read(1, &mut [0; 4]).is_err());
+    }
+
+    #[test]
+    fn overwrites_keys() {
+        let mut flash = RamFlash::new(2);
+        let mut store = Store::open(&mut flash).unwrap();
+
+        store.write(1, b"first").unwrap();
+        store.write(1, b"second").unwrap();
+
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"second"[..]));
+
+        // Writing the same value again leaves the flash alone.
+        let position = store.position;
+        store.write(1, b"second").unwrap();
+        assert_eq!(store.position, position);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"second"[..]));
+    }
+
+    #[test]
+    fn rotates_and_collects_across_sectors() {
+        let mut flash = RamFlash::new(3);
+        let mut store = Store::open(&mut flash).unwrap();
+
+        store.write(1, b"kept while others are rewritten").unwrap();
+
+        // Each record takes 48 bytes, so this cycles through all sectors several times.
+        for i in 0..40u8 {
+            store.write(2, &[i; 40]).unwrap();
+        }
+
+        assert!(store.sequence > 3);
+        assert_eq!(
+            read(&mut store, 1).as_deref(),
+            Some(&b"kept while others are rewritten"[..])
+        );
+        assert_eq!(read(&mut store, 2), Some(vec![39; 40]));
+
+        let mut store = Store::open(&mut flash).unwrap();
+
+        assert_eq!(
+            read(&mut store, 1).as_deref(),
+            Some(&b"kept while others are rewritten"[..])
+        );
+        assert_eq!(read(&mut store, 2), Some(vec![39; 40]));
+    }
+
+    #[test]
+    fn falls_back_to_previous_value_after_torn_record() {
+        let mut flash = RamFlash::new(2);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        store.write(1, b"previous value").unwrap();
+
+        // The header and half of the value reach the flash.
+        flash.budget = Some(RECORD_HEADER_SIZE as usize + 8);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert!(store.write(1, b"torn by a reset").is_err());
+
+        flash.budget = None;
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"previous value"[..]));
+
+        // Writing continues after the torn record.
+        store.write(1, b"next value").unwrap();
+        store.write(2, b"other").unwrap();
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"next value"[..]));
+        assert_eq!(read(&mut store, 2).as_deref(), Some(&b"other"[..]));
+    }
+
+    #[test]
+    fn falls_back_to_previous_value_after_torn_header() {
+        let mut flash = RamFlash::new(2);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        store.write(1, b"previous value").unwrap();
+
+        flash.budget = Some(4);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert!(store.write(1, b"torn by a reset").is_err());
+
+        flash.budget = None;
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(read(&mut store, 1).as_deref(), Some(&b"previous value"[..]));
+    }
+
+    #[test]
+    fn open_resumes_interrupted_rotation() {
+        let mut flash = RamFlash::new(3);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        store.write(1, b"survives the rotation").unwrap();
+
+        // Each record takes 48 bytes: four fill the first sector, five the second one.
+        for i in 0..9u8 {
+            store.write(2, &[i; 40]).unwrap();
+        }
+
+        assert_eq!(store.active, 1);
+
+        // Power is lost right after the third sector is activated, before the record still current
+        // in the first one was copied.
+        flash.budget = Some(SECTOR_HEADER_SIZE as usize);
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert!(store.write(2, &[9; 40]).is_err());
+
+        flash.budget = None;
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(store.active, 2);
+
+        // The oldest sector was collected into the active one and is the erased spare again.
+        assert!(store.is_erased(0, SECTOR_SIZE as u32).unwrap());
+        assert_eq!(
+            read(&mut store, 1).as_deref(),
+            Some(&b"survives the rotation"[..])
+        );
+        assert_eq!(read(&mut store, 2), Some(vec![8; 40]));
+
+        // Later rotations keep working.
+        for i in 10..30u8 {
+            store.write(2, &[i; 40]).unwrap();
+        }
+
+        let mut store = Store::open(&mut flash).unwrap();
+        assert_eq!(
+            read(&mut store, 1).as_deref(),
+            Some(&b"survives the rotation"[..])
+        );
+        assert_eq!(read(&mut store, 2), Some(vec![29; 40]));
+    }
+}
@@ -6,12 +6,17 @@ use reqwless::{
 
 use crate::errors::Error;
 
-pub async fn get_todos<'a, T, D>(
+/// Maximum length of a todo in bytes, longer ones are cut off.
+const MAX_TODO_LENGTH: usize = 128;
+
+pub type Todo = heapless::String<MAX_TODO_LENGTH>;
+
+/// Fetch the first `N` todos, one per non-empty line of the response.
+pub async fn get_todos<const N: usize, T, D>(
     client: &mut HttpClient<'_, T, D>,
     url: &str,
     auth: &str,
-    read_buffer: &'a mut [u8],
-) -> Result<impl Iterator<Item = &'a str>, Error>
+) -> Result<heapless::Vec<Todo, N>, Error>
 where
     T: TcpConnect,
     D: Dns,
@@ -21,6 +26,7 @@ where
     let headers = [("Authorization", auth)];
 
     let mut write_buffer = [0u8; 1024];
+    let mut read_buffer = [0u8; 1024];
 
     let size = client
         .request(Method::GET, url)
@@ -32,12 +38,27 @@ where
         .map_err(|_| Error::Http("failed to send request"))?
         .body()
         .reader()
-        .read_to_end(read_buffer)
+        .read_to_end(&mut read_buffer)
         .await
         .map_err(|_| Error::Http("failed to read into buffer"))?;
 
     Ok(core::str::from_utf8(&read_buffer[..size])
         .map_err(|_| Error::ParseUtf8)?
         .split('\n')
-        .filter(|s| !s.is_empty()))
+        .filter(|s| !s.is_empty())
+        .take(N)
+        .map(truncate)
+        .collect())
+}
+
+fn truncate(s: &str) -> Todo {
+    let mut todo = Todo::new();
+
+    for c in s.chars() {
+        if todo.push(c).is_err() {
+            break;
+        }
+    }
+
+    todo
 }
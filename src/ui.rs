@@ -7,19 +7,26 @@ use core::hash::BuildHasher;
 
 use embedded_graphics::image::{Image, ImageRaw};
 use embedded_graphics::mono_font::{MonoTextStyle, MonoTextStyleBuilder};
-use embedded_graphics::prelude::{Drawable, Point, Primitive};
-use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle};
+use embedded_graphics::prelude::{DrawTarget, Drawable, Point, Primitive, Size};
+use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle, Rectangle};
 use embedded_graphics::text::{Alignment, Baseline, Text, TextStyle, TextStyleBuilder};
-use epd_waveshare::epd7in5_v2::Display7in5;
 use epd_waveshare::prelude::*;
-use esp_backtrace as _;
 use heapless::{String, format};
-use jiff::civil::{Date, Weekday};
+use jiff::civil::Date;
 use jiff::fmt::strtime;
 use rapidhash::fast;
 
+use crate::air_quality::AirQuality;
+use crate::errors::Error;
 use crate::ics::Either;
-use crate::{icons, ics, weather};
+use crate::locale::Locale;
+use crate::status::{Refresh, Source};
+use crate::units::Units;
+use crate::{alerts, icons, ics, weather};
+
+use text::{Overflow, draw_fitted};
+
+mod text;
 
 const SPLEEN_HUGE_STYLE: MonoTextStyle<Color> = MonoTextStyleBuilder::new()
     .font(&FONT_SPLEEN_16_32)
@@ -39,43 +46,30 @@ const SPLEEN_SMALL_STYLE: MonoTextStyle<Color> = MonoTextStyleBuilder::new()
     .background_color(Color::Black)
     .build();
 
+const SPLEEN_SMALL_INVERTED_STYLE: MonoTextStyle<Color> = MonoTextStyleBuilder::new()
+    .font(&FONT_SPLEEN_8_16)
+    .text_color(Color::Black)
+    .background_color(Color::White)
+    .build();
+
+/// Width of a column of the hourly forecast.
+const HOURLY_COLUMN_WIDTH: i32 = 72;
+
+/// UV index from which on a badge is shown in the daily forecast.
+const UV_BADGE_THRESHOLD: f32 = 6.0;
+
 const LINE_STYLE: PrimitiveStyle<Color> = PrimitiveStyle::with_stroke(Color::White, 1);
 
 const TOP_TEXT_STYLE: TextStyle = TextStyleBuilder::new().baseline(Baseline::Top).build();
 
-const BOTTOM_TEXT_STYLE: TextStyle = TextStyleBuilder::new().baseline(Baseline::Bottom).build();
-
-fn localized_weekday(weekday: Weekday) -> &'static str {
-    match weekday {
-        Weekday::Monday => "Mo",
-        Weekday::Tuesday => "Di",
-        Weekday::Wednesday => "Mi",
-        Weekday::Thursday => "Do",
-        Weekday::Friday => "Fr",
-        Weekday::Saturday => "Sa",
-        Weekday::Sunday => "So",
-    }
-}
+/// Draw target of a single widget, usually the display cropped to the widget's area of the
+/// [`Layout`](crate::layout::Layout) so that coordinates are relative to the area's top left
+/// corner.
+pub trait Canvas: DrawTarget<Color = Color, Error = Infallible> {}
 
-fn localized_month(month: i8) -> &'static str {
-    match month {
-        1 => "JAN",
-        2 => "FEB",
-        3 => "MÄR",
-        4 => "APR",
-        5 => "MAI",
-        6 => "JUN",
-        7 => "JUL",
-        8 => "AUG",
-        9 => "SEP",
-        10 => "OKT",
-        11 => "NOV",
-        12 => "DEZ",
-        _ => "???",
-    }
-}
+impl<T: DrawTarget<Color = Color, Error = Infallible>> Canvas for T {}
 
-pub fn draw_date(display: &mut Display7in5, date: Date) -> Result<(), Infallible> {
+pub fn draw_date(display: &mut impl Canvas, date: Date) -> Result<(), Infallible> {
     let day: String<2> = format!("{:02}", date.day()).expect("formatting day");
     let month: String<2> = format!("{:02}", date.month()).expect("formatting month");
 
@@ -88,9 +82,12 @@ pub fn draw_date(display: &mut Display7in5, date: Date) -> Result<(), Infallible
     Ok(())
 }
 
-pub fn draw_hourly_weather(
-    display: &mut Display7in5,
-    forecast: impl Iterator<Item = weather::HourlyForecast>,
+pub fn draw_hourly_weather<'a>(
+    display: &mut impl Canvas,
+    forecast: impl Iterator<Item = &'a weather::HourlyForecast>,
+    today: Date,
+    units: &Units,
+    locale: Locale,
 ) -> Result<(), Infallible> {
     let text_style = TextStyleBuilder::new()
         .alignment(Alignment::Center)
@@ -98,27 +95,39 @@ pub fn draw_hourly_weather(
         .build();
 
     for (index, forecast) in forecast.enumerate() {
-        let temperature: String<8> = format!("{:.0}°C", fix_minus_zero(forecast.temperature))
-            .expect("formatting temperature");
+        let temperature = units.format_temperature(forecast.temperature);
+
+        let hour = forecast.time.hour();
 
-        let hour: String<8> = format!("{:0>2}:00", forecast.hour).unwrap();
+        let label: String<5> = format!("{hour:02}:00").expect("formatting hour label");
 
-        let x = (index as i32 + 1) * 72;
+        let x = HOURLY_COLUMN_WIDTH / 2 + index as i32 * HOURLY_COLUMN_WIDTH;
 
-        Text::with_text_style(&hour, Point::new(x, 3), SPLEEN_SMALL_STYLE, text_style)
+        // The day goes on its own line, as it does not fit next to the hour.
+        if forecast.time.date() != today {
+            Text::with_text_style(
+                locale.tomorrow(),
+                Point::new(x, 3),
+                SPLEEN_SMALL_STYLE,
+                text_style,
+            )
+            .draw(display)?;
+        }
+
+        Text::with_text_style(&label, Point::new(x, 19), SPLEEN_SMALL_STYLE, text_style)
             .draw(display)?;
 
         Text::with_text_style(
             &temperature,
-            Point::new(x, 54),
+            Point::new(x, 70),
             SPLEEN_SMALL_STYLE,
             text_style,
         )
         .draw(display)?;
 
         Image::new(
-            weather::hourly_icon(forecast.hour, forecast.weather_code),
-            Point::new(x - 16, 17),
+            weather::hourly_icon(hour as u8, forecast.weather_code),
+            Point::new(x - 16, 33),
         )
         .draw(display)?;
     }
@@ -126,11 +135,16 @@ pub fn draw_hourly_weather(
     Ok(())
 }
 
-pub fn draw_daily_weather(
-    display: &mut Display7in5,
-    forecast: impl Iterator<Item = weather::DailyForecast>,
+/// Draws the daily forecast. If `apparent` is set, "feels like" temperatures are shown where
+/// available.
+pub fn draw_daily_weather<'a>(
+    display: &mut impl Canvas,
+    forecast: impl Iterator<Item = &'a weather::DailyForecast>,
+    apparent: bool,
+    units: &Units,
+    locale: Locale,
 ) -> Result<(), Infallible> {
-    Line::new(Point::new(3 * 72 + 39, 8), Point::new(3 * 72 + 39, 50))
+    Line::new(Point::new(3, 8), Point::new(3, 50))
         .into_styled(LINE_STYLE)
         .draw(display)?;
 
@@ -140,23 +154,45 @@ pub fn draw_daily_weather(
         .build();
 
     for (index, forecast) in forecast.enumerate() {
-        let temperature: String<16> = format!(
-            "{:.0}°C/{:.0}°C",
-            fix_minus_zero(forecast.min_temperature),
-            fix_minus_zero(forecast.max_temperature)
-        )
-        .expect("formatting temperature");
+        let (min_temperature, max_temperature) = if apparent {
+            (
+                forecast
+                    .apparent_min_temperature
+                    .unwrap_or(forecast.min_temperature),
+                forecast
+                    .apparent_max_temperature
+                    .unwrap_or(forecast.max_temperature),
+            )
+        } else {
+            (forecast.min_temperature, forecast.max_temperature)
+        };
+
+        let temperature = units.format_temperature_range(min_temperature, max_temperature);
 
-        let x = (index as i32 + 1) * 72 + 3 * 72 + 5;
+        let x = 41 + index as i32 * 72;
 
         Text::with_text_style(
-            localized_weekday(forecast.date.weekday()),
+            locale.weekday(forecast.date.weekday()),
             Point::new(x, 3),
             SPLEEN_SMALL_STYLE,
             text_style,
         )
         .draw(display)?;
 
+        if let Some(uv_index) = forecast.uv_index_max
+            && uv_index >= UV_BADGE_THRESHOLD
+        {
+            let badge: String<8> = format!("UV{uv_index:.0}").expect("formatting UV index");
+
+            Text::with_text_style(
+                &badge,
+                Point::new(x + 10, 3),
+                SPLEEN_SMALL_INVERTED_STYLE,
+                TOP_TEXT_STYLE,
+            )
+            .draw(display)?;
+        }
+
         Text::with_text_style(
             &temperature,
             Point::new(x, 54),
@@ -175,26 +211,137 @@ pub fn draw_daily_weather(
     Ok(())
 }
 
+/// Draws a weather warning as an inverted banner in place of the daily forecast.
+pub fn draw_alert(
+    display: &mut impl Canvas,
+    alert: &alerts::Alert,
+    locale: Locale,
+) -> Result<(), Infallible> {
+    const BANNER_X: i32 = 7;
+
+    Line::new(Point::new(3, 8), Point::new(3, 50))
+        .into_styled(LINE_STYLE)
+        .draw(display)?;
+
+    let banner_width = display.bounding_box().size.width - BANNER_X as u32;
+
+    Rectangle::new(Point::new(BANNER_X, 2), Size::new(banner_width, 68))
+        .into_styled(PrimitiveStyle::with_fill(Color::White))
+        .draw(display)?;
+
+    let chars_per_line = text::fitting_chars(SPLEEN_SMALL_INVERTED_STYLE.font, banner_width - 8);
+    let (first, rest) = text::split_line(&alert.headline, chars_per_line);
+    let second = text::ellipsize(rest, chars_per_line);
+
+    for (index, line) in [first, &second].into_iter().enumerate() {
+        Text::with_text_style(
+            line,
+            Point::new(BANNER_X + 4, 4 + index as i32 * 16),
+            SPLEEN_SMALL_INVERTED_STYLE,
+            TOP_TEXT_STYLE,
+        )
+        .draw(display)?;
+    }
+
+    let validity: String<32> = match &alert.expires {
+        Some(expires) => format!(
+            "{} {}, {}",
+            locale.until(),
+            locale.weekday(expires.date().weekday()),
+            strtime::format("%H:%M", expires.time()).unwrap()
+        )
+        .expect("formatting alert validity"),
+        None => String::try_from(locale.until_further_notice()).unwrap(),
+    };
+
+    Text::with_text_style(
+        &validity,
+        Point::new(BANNER_X + 4, 52),
+        SPLEEN_SMALL_INVERTED_STYLE,
+        TOP_TEXT_STYLE,
+    )
+    .draw(display)?;
+
+    Ok(())
+}
+
+/// Draws a single row with the air quality category and up to three pollen types with a bar per
+/// burden level, as many as fit.
+pub fn draw_air_quality(
+    display: &mut impl Canvas,
+    air_quality: &AirQuality,
+    locale: Locale,
+) -> Result<(), Infallible> {
+    const POLLEN_X: i32 = 168;
+    // Three bars of 6 pixels, 8 pixels apart.
+    const BARS_WIDTH: i32 = 3 * 8 - 2;
+
+    let label: String<32> = format!(
+        "{} {}",
+        locale.air(),
+        locale.aqi_category(air_quality.category())
+    )
+    .expect("formatting air quality");
+
+    Text::with_text_style(&label, Point::zero(), SPLEEN_SMALL_STYLE, TOP_TEXT_STYLE)
+        .draw(display)?;
+
+    let width = display.bounding_box().size.width as i32;
+    let mut x = POLLEN_X;
+
+    for (pollen, level) in air_quality.highest_pollen().into_iter().take(3) {
+        let name = locale.pollen(pollen);
+
+        // The highest pollen come first, so the ones left out are the least relevant.
+        if x + text::text_width(SPLEEN_SMALL_STYLE.font, name) as i32 + 4 + BARS_WIDTH > width {
+            break;
+        }
+
+        x = Text::with_text_style(name, Point::new(x, 0), SPLEEN_SMALL_STYLE, TOP_TEXT_STYLE)
+            .draw(display)?
+            .x
+            + 4;
+
+        for bar in 0..3 {
+            let style = if bar < level {
+                PrimitiveStyle::with_fill(Color::White)
+            } else {
+                LINE_STYLE
+            };
+
+            Rectangle::new(Point::new(x + bar as i32 * 8, 3), Size::new(6, 10))
+                .into_styled(style)
+                .draw(display)?;
+        }
+
+        x += 3 * 8 + 12;
+    }
+
+    Ok(())
+}
+
 fn draw_vertical_month_label(
-    display: &mut Display7in5,
+    display: &mut impl Canvas,
     month: i8,
     start_y: i32,
     end_y: i32,
     x: i32,
+    locale: Locale,
 ) -> Result<(), Infallible> {
-    let month_name = localized_month(month);
+    let month_name = locale.month(month);
     let char_height = 16; // Height of SPLEEN_SMALL font
-    let total_text_height = 3 * char_height; // We only have three-character abbreviated months
     let span_height = end_y - start_y;
 
-    let text_start_y = start_y + (span_height - total_text_height) / 2;
+    // Groups of a single all-day event are too short for the whole label.
+    let chars = (span_height / char_height).clamp(1, 3);
+    let text_start_y = start_y + (span_height - chars * char_height) / 2;
 
     let text_style = TextStyleBuilder::new()
         .alignment(Alignment::Center)
         .baseline(Baseline::Top)
         .build();
 
-    for (index, char) in month_name.chars().enumerate() {
+    for (index, char) in month_name.chars().take(chars as usize).enumerate() {
         let mut char_buf: String<4> = String::new();
         char_buf.push(char).ok();
 
@@ -211,99 +358,113 @@ fn draw_vertical_month_label(
 }
 
 pub fn draw_events(
-    display: &mut Display7in5,
+    display: &mut impl Canvas,
     events: &[ics::Event],
     today: Date,
+    locale: Locale,
 ) -> Result<(), Infallible> {
     const MONTH_COL_X: i32 = 6; // X position for month label (centered)
     const MONTH_LINE_X: i32 = 20; // X position for vertical month line
     const DAY_COL_X: i32 = 46; // X position for day column (shifted right)
     const EVENT_COL_X: i32 = 70; // X position for event details (shifted right)
-    const EVENT_HEIGHT: i32 = 60;
-    const FIRST_EVENT_Y: i32 = 78; // Y offset for first event
 
     let text_style = TextStyleBuilder::new()
         .alignment(Alignment::Center)
         .baseline(Baseline::Top)
         .build();
 
+    let Size { width, height } = display.bounding_box().size;
+    let visible = visible_events(events, height as i32);
+    let hidden = events.len() - visible;
+    let events = &events[..visible];
+
     struct MonthGroup {
         month: i8,
-        start_index: usize,
-        end_index: usize,
+        start_y: i32,
+        end_y: i32,
     }
 
     let mut month_groups: heapless::Vec<MonthGroup, 12> = heapless::Vec::new();
-    let mut current_month = events[0].start.date().month();
-    let mut group_start = 0;
-
-    for (index, event) in events.iter().enumerate() {
-        let event_month = event.start.date().month();
-        if event_month != current_month {
-            month_groups
-                .push(MonthGroup {
-                    month: current_month,
-                    start_index: group_start,
-                    end_index: index - 1,
-                })
-                .ok();
-
-            current_month = event_month;
-            group_start = index;
+    let mut y_offset = 0;
+
+    for event in events {
+        let month = event.start.date().month();
+        let end_y = y_offset + event_height(event);
+
+        match month_groups.last_mut() {
+            Some(group) if group.month == month => group.end_y = end_y,
+            _ => {
+                month_groups
+                    .push(MonthGroup {
+                        month,
+                        start_y: y_offset,
+                        end_y,
+                    })
+                    .ok();
+            }
         }
-    }
 
-    month_groups
-        .push(MonthGroup {
-            month: current_month,
-            start_index: group_start,
-            end_index: events.len() - 1,
-        })
-        .ok();
+        y_offset = end_y;
+    }
 
     for group in &month_groups {
-        let start_y = FIRST_EVENT_Y + group.start_index as i32 * EVENT_HEIGHT;
-        let end_y = FIRST_EVENT_Y + (group.end_index as i32 + 1) * EVENT_HEIGHT;
+        let (start_y, end_y) = (group.start_y, group.end_y);
 
         Line::new(
-            Point::new(MONTH_LINE_X, start_y + 14),
-            Point::new(MONTH_LINE_X, end_y + 2),
+            Point::new(MONTH_LINE_X, start_y + 4),
+            Point::new(MONTH_LINE_X, end_y - 8),
         )
         .into_styled(LINE_STYLE)
         .draw(display)?;
 
         Line::new(
-            Point::new(MONTH_LINE_X, start_y + 14),
-            Point::new(MONTH_LINE_X + 4, start_y + 14),
+            Point::new(MONTH_LINE_X, start_y + 4),
+            Point::new(MONTH_LINE_X + 4, start_y + 4),
         )
         .into_styled(LINE_STYLE)
         .draw(display)?;
 
         Line::new(
-            Point::new(MONTH_LINE_X, end_y + 2),
-            Point::new(MONTH_LINE_X + 4, end_y + 2),
+            Point::new(MONTH_LINE_X, end_y - 8),
+            Point::new(MONTH_LINE_X + 4, end_y - 8),
         )
         .into_styled(LINE_STYLE)
         .draw(display)?;
 
         // Draw month label vertically centered
-        draw_vertical_month_label(display, group.month, start_y + 12, end_y + 6, MONTH_COL_X);
+        draw_vertical_month_label(
+            display,
+            group.month,
+            start_y + 2,
+            end_y - 4,
+            MONTH_COL_X,
+            locale,
+        )?;
     }
 
-    for (index, event) in events.iter().enumerate() {
-        let y_offset = FIRST_EVENT_Y + index as i32 * EVENT_HEIGHT;
+    let mut y_offset = 0;
+
+    for event in events {
+        let all_day = is_all_day(event);
+
+        // Compact all-day events take a single line of the large font.
+        let (day_y, arrow_y) = if all_day {
+            (y_offset + 4, y_offset + 8)
+        } else {
+            (y_offset + 10, y_offset + 14)
+        };
 
         if event.start.date() == today {
             Line::new(
-                Point::new(MONTH_LINE_X, y_offset + 24),
-                Point::new(MONTH_LINE_X + 8, y_offset + 32),
+                Point::new(MONTH_LINE_X, arrow_y),
+                Point::new(MONTH_LINE_X + 8, arrow_y + 8),
             )
             .into_styled(LINE_STYLE)
             .draw(display)?;
 
             Line::new(
-                Point::new(MONTH_LINE_X + 8, y_offset + 32),
-                Point::new(MONTH_LINE_X, y_offset + 40),
+                Point::new(MONTH_LINE_X + 8, arrow_y + 8),
+                Point::new(MONTH_LINE_X, arrow_y + 16),
             )
             .into_styled(LINE_STYLE)
             .draw(display)?;
@@ -313,77 +474,330 @@ pub fn draw_events(
 
         Text::with_text_style(
             &day,
-            Point::new(DAY_COL_X, y_offset + 20),
+            Point::new(DAY_COL_X, day_y),
             SPLEEN_LARGE_STYLE,
             text_style,
         )
         .draw(display)?;
 
+        // The end date of all-day events is exclusive.
+        let until: Option<String<32>> = match (&event.start, &event.end) {
+            (Either::Date(start_date), Either::Date(end_date)) => end_date
+                .yesterday()
+                .ok()
+                .filter(|last_date| last_date > start_date)
+                .map(|last_date| {
+                    format!(
+                        "{} {}, {}",
+                        locale.until(),
+                        locale.weekday(last_date.weekday()),
+                        strtime::format(locale.date_format(), last_date).unwrap()
+                    )
+                    .unwrap()
+                }),
+            _ => None,
+        };
+
+        let until_width = until.as_ref().map_or(0, |until| {
+            text::text_width(SPLEEN_SMALL_STYLE.font, until) + 8
+        });
+
+        // Draw event column with summary and times
+        let summary_end = draw_fitted(
+            display,
+            &event.summary,
+            Point::new(EVENT_COL_X, day_y),
+            width.saturating_sub(EVENT_COL_X as u32 + until_width),
+            SPLEEN_LARGE_STYLE,
+            Overflow::Wrap(SPLEEN_SMALL_STYLE),
+        )?;
+
+        if let Some(until) = until {
+            Text::with_text_style(
+                &until,
+                Point::new(summary_end.x + 8, day_y + 6),
+                SPLEEN_SMALL_STYLE,
+                TOP_TEXT_STYLE,
+            )
+            .draw(display)?;
+        }
+
+        if all_day {
+            y_offset += ALL_DAY_EVENT_HEIGHT;
+            continue;
+        }
+
         Text::with_text_style(
-            localized_weekday(event.start.date().weekday()),
-            Point::new(DAY_COL_X, y_offset + 46),
+            locale.weekday(event.start.date().weekday()),
+            Point::new(DAY_COL_X, y_offset + 36),
             SPLEEN_SMALL_STYLE,
             text_style,
         )
         .draw(display)?;
 
-        // Draw event column with summary and times
+        let start = format_either(event.start.clone(), locale);
+        let end = format_either(event.end.clone(), locale);
+        let duration: String<36> = format!("{start} - {end}").unwrap();
+
         Text::with_text_style(
-            &event.summary,
-            Point::new(EVENT_COL_X, y_offset + 20),
-            SPLEEN_LARGE_STYLE,
+            &duration,
+            Point::new(EVENT_COL_X, y_offset + 36),
+            SPLEEN_SMALL_STYLE,
             TOP_TEXT_STYLE,
         )
         .draw(display)?;
 
-        let (duration_x, duration): (i32, String<32>) = match (&event.start, &event.end) {
-            (Either::Date(_), Either::Date(end_date)) => {
-                let line_y = y_offset + 46 + 8;
+        y_offset += EVENT_HEIGHT;
+    }
 
-                Line::new(
-                    Point::new(EVENT_COL_X, line_y),
-                    Point::new(EVENT_COL_X + 16, line_y),
-                )
-                .into_styled(LINE_STYLE)
-                .draw(display)?;
+    if hidden > 0 {
+        let more: String<24> = format!("+{hidden} {}", locale.more()).unwrap();
 
-                let end_formatted = format!(
-                    "bis {}, {}",
-                    localized_weekday(end_date.weekday()),
-                    strtime::format("%d.%m.", *end_date).unwrap()
-                )
-                .unwrap();
+        Text::with_text_style(
+            &more,
+            Point::new(EVENT_COL_X, y_offset + 2),
+            SPLEEN_SMALL_STYLE,
+            TOP_TEXT_STYLE,
+        )
+        .draw(display)?;
+    }
 
-                (EVENT_COL_X + 24, end_formatted)
-            }
-            _ => {
-                let start = format_either(event.start.clone());
-                let end = format_either(event.end.clone());
-                (EVENT_COL_X, format!("{start} - {end}").unwrap())
-            }
-        };
+    Ok(())
+}
 
+/// Height of a timed event with its day, weekday and times.
+const EVENT_HEIGHT: i32 = 60;
+/// Height of an all-day event drawn as a single line.
+const ALL_DAY_EVENT_HEIGHT: i32 = 32;
+/// Height of the "+N more" line below the agenda.
+const MORE_EVENTS_HEIGHT: i32 = 20;
+
+fn is_all_day(event: &ics::Event) -> bool {
+    matches!(event.start, Either::Date(_))
+}
+
+fn event_height(event: &ics::Event) -> i32 {
+    if is_all_day(event) {
+        ALL_DAY_EVENT_HEIGHT
+    } else {
+        EVENT_HEIGHT
+    }
+}
+
+/// Returns how many of `events` fit into `height` pixels, leaving room for the "+N more" line if
+/// not all of them do.
+fn visible_events(events: &[ics::Event], height: i32) -> usize {
+    let total: i32 = events.iter().map(event_height).sum();
+
+    if total <= height {
+        return events.len();
+    }
+
+    let mut y = 0;
+
+    events
+        .iter()
+        .take_while(|event| {
+            y += event_height(event);
+            y <= height - MORE_EVENTS_HEIGHT
+        })
+        .count()
+}
+
+/// Draws a full screen message about `error` in place of the dashboard, e.g. when no data could be
+/// fetched at all.
+pub fn draw_error(
+    display: &mut impl Canvas,
+    error: &Error,
+    last_update: Option<&jiff::Zoned>,
+    locale: Locale,
+) -> Result<(), Infallible> {
+    const X: i32 = 24;
+    const Y: i32 = 320;
+
+    let width = display.bounding_box().size.width - 2 * X as u32;
+
+    Text::with_text_style(
+        locale.error(),
+        Point::new(X, Y),
+        SPLEEN_HUGE_STYLE,
+        TOP_TEXT_STYLE,
+    )
+    .draw(display)?;
+
+    // The debug representation names the variant and its context.
+    let message: String<96> = format!("{error:?}").unwrap_or_default();
+    let chars_per_line = text::fitting_chars(SPLEEN_SMALL_STYLE.font, width);
+    let (first, rest) = text::split_line(&message, chars_per_line);
+    let second = text::ellipsize(rest, chars_per_line);
+
+    for (index, line) in [first, &second].into_iter().enumerate() {
         Text::with_text_style(
-            &duration,
-            Point::new(duration_x, y_offset + 46),
+            line,
+            Point::new(X, Y + 48 + index as i32 * 16),
             SPLEEN_SMALL_STYLE,
             TOP_TEXT_STYLE,
         )
         .draw(display)?;
     }
 
+    let last_update: String<64> = match last_update {
+        Some(time) => format!(
+            "{}: {}, {} {}",
+            locale.last_update(),
+            locale.weekday(time.date().weekday()),
+            strtime::format(locale.date_format(), time.date()).unwrap(),
+            strtime::format("%H:%M", time.time()).unwrap()
+        ),
+        None => format!("{}: {}", locale.last_update(), locale.never()),
+    }
+    .expect("formatting last update");
+
+    draw_fitted(
+        display,
+        &last_update,
+        Point::new(X, Y + 96),
+        width,
+        SPLEEN_SMALL_STYLE,
+        Overflow::Ellipsis,
+    )?;
+
+    Ok(())
+}
+
+/// Marks a widget whose data could not be refreshed with an inverted label in its top right
+/// corner, e.g. "stale since 14:00" for data `fetched` earlier or just "!" if there is none or the
+/// label does not fit.
+pub fn draw_stale_marker(
+    display: &mut impl Canvas,
+    fetched: Option<&jiff::Zoned>,
+    today: Date,
+    locale: Locale,
+) -> Result<(), Infallible> {
+    let width = display.bounding_box().size.width;
+
+    let label: Option<String<32>> = fetched.and_then(|fetched| {
+        let format = if fetched.date() == today {
+            "%H:%M"
+        } else {
+            locale.date_format()
+        };
+
+        format!(
+            "! {} {}",
+            locale.stale_since(),
+            strtime::format(format, fetched).unwrap()
+        )
+        .ok()
+    });
+
+    let label = label
+        .as_deref()
+        .filter(|label| text::text_width(SPLEEN_SMALL_STYLE.font, label) <= width)
+        .unwrap_or("!");
+
+    Text::with_text_style(
+        label,
+        Point::new(
+            width.saturating_sub(text::text_width(SPLEEN_SMALL_STYLE.font, label)) as i32,
+            0,
+        ),
+        SPLEEN_SMALL_INVERTED_STYLE,
+        TOP_TEXT_STYLE,
+    )
+    .draw(display)?;
+
+    Ok(())
+}
+
+/// Draws a single line with the time of this and the next update, the Wi-Fi signal strength and
+/// the time since the last NTP sync on the left and a label for each fetched source on the right,
+/// inverted if fetching it failed.
+pub fn draw_status_bar(
+    display: &mut impl Canvas,
+    now: &jiff::Zoned,
+    next_update: &jiff::Zoned,
+    rssi: Option<i32>,
+    since_sync: Option<embassy_time::Duration>,
+    refresh: &Refresh,
+    locale: Locale,
+) -> Result<(), Infallible> {
+    let font = SPLEEN_SMALL_STYLE.font;
+    let mut x = display.bounding_box().size.width as i32;
+
+    for source in Source::ALL.into_iter().rev() {
+        let style = match refresh.result(source) {
+            Some(true) => SPLEEN_SMALL_STYLE,
+            Some(false) => SPLEEN_SMALL_INVERTED_STYLE,
+            None => continue,
+        };
+
+        let label = locale.source(source);
+
+        x -= text::text_width(font, label) as i32;
+        Text::with_text_style(label, Point::new(x, 0), style, TOP_TEXT_STYLE).draw(display)?;
+        x -= font.character_size.width as i32;
+    }
+
+    let rssi: String<8> = match rssi {
+        Some(rssi) => format!("{rssi}dBm"),
+        None => format!("--dBm"),
+    }
+    .expect("formatting RSSI");
+
+    let since_sync: String<8> = match since_sync.map(|since_sync| since_sync.as_secs() / 60) {
+        Some(minutes) if minutes < 60 => format!("{minutes}m"),
+        Some(minutes) if minutes < 48 * 60 => format!("{}h", minutes / 60),
+        Some(minutes) => format!("{}d", minutes / (24 * 60)),
+        None => format!("--"),
+    }
+    .expect("formatting NTP sync age");
+
+    let summary: String<48> = format!(
+        "{}>{} {rssi} NTP {since_sync}",
+        strtime::format("%H:%M", now.time()).unwrap(),
+        strtime::format("%H:%M", next_update.time()).unwrap(),
+    )
+    .expect("formatting status");
+
+    draw_fitted(
+        display,
+        &summary,
+        Point::zero(),
+        x.max(0) as u32,
+        SPLEEN_SMALL_STYLE,
+        Overflow::Ellipsis,
+    )?;
+
     Ok(())
 }
 
+/// Maximum number of todos drawn.
+pub const MAX_TODOS: usize = 3;
+
+/// Height of a single todo line.
+const TODO_HEIGHT: u32 = 30;
+
+/// Returns the height taken by `count` todos at the bottom of the todo area.
+pub fn todo_list_height(count: usize) -> u32 {
+    match count.min(MAX_TODOS) {
+        0 => 0,
+        count => count as u32 * TODO_HEIGHT + 4,
+    }
+}
+
 pub fn draw_todos<'a>(
-    display: &mut Display7in5,
+    display: &mut impl Canvas,
     todos: impl Iterator<Item = &'a str>,
 ) -> Result<(), Infallible> {
-    let mut y = 790;
+    let Size { width, height } = display.bounding_box().size;
+
+    // Todos are stacked upwards from the bottom of the area, `y` is the bottom of each line.
+    let mut y = height as i32 - 4;
 
     for todo in todos
-        .take(3)
-        .collect::<heapless::Vec<_, 3>>()
+        .take(MAX_TODOS)
+        .collect::<heapless::Vec<_, MAX_TODOS>>()
         .into_iter()
         .rev()
     {
@@ -395,21 +809,25 @@ pub fn draw_todos<'a>(
                 .draw(display)?;
         }
 
-        Text::with_text_style(
+        draw_fitted(
+            display,
             todo,
-            Point::new(24, y),
+            Point::new(
+                24,
+                y + 1 - SPLEEN_LARGE_STYLE.font.character_size.height as i32,
+            ),
+            width.saturating_sub(24),
             SPLEEN_LARGE_STYLE,
-            BOTTOM_TEXT_STYLE,
-        )
-        .draw(display)?;
+            Overflow::Ellipsis,
+        )?;
 
-        y -= 30;
+        y -= TODO_HEIGHT as i32;
     }
 
     Ok(())
 }
 
-fn format_either(either: Either) -> String<16> {
+fn format_either(either: Either, locale: Locale) -> String<16> {
     match either {
         ics::Either::DateTime(zoned) => {
             format!("{}", strtime::format("%H:%M", zoned.time()).unwrap())
@@ -417,17 +835,13 @@ fn format_either(either: Either) -> String<16> {
         }
         ics::Either::Date(date) => format!(
             "{}, {}",
-            localized_weekday(date.weekday()),
-            strtime::format("%d.%m", date).unwrap()
+            locale.weekday(date.weekday()),
+            strtime::format(locale.date_format(), date).unwrap()
         )
         .expect("formatting date"),
     }
 }
 
-fn fix_minus_zero(num: f32) -> f32 {
-    if num > -1.0 && num < 0.0 { 0.0 } else { num }
-}
-
 /// Returns an icon indexed into the pyairvander icon set if `s` contains a substring `@foobar`.
 fn get_icon_when_assigned(s: &str) -> Option<ImageRaw<'static, Color>> {
     let state = fast::SeedableState::new(0);
@@ -440,3 +854,82 @@ fn get_icon_when_assigned(s: &str) -> Option<ImageRaw<'static, Color>> {
         })
         .map(|index| icons::pyairvander_icon(index))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Canvas recording the rightmost pixel drawn.
+    struct Extent {
+        size: Size,
+        right: i32,
+    }
+
+    impl Extent {
+        fn new(width: u32, height: u32) -> Self {
+            Self {
+                size: Size::new(width, height),
+                right: -1,
+            }
+        }
+    }
+
+    impl embedded_graphics::prelude::OriginDimensions for Extent {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    impl DrawTarget for Extent {
+        type Color = Color;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+        {
+            for embedded_graphics::Pixel(point, _) in pixels {
+                self.right = self.right.max(point.x);
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn pollen_fit_air_quality_row() {
+        // All pollen at the highest level, so that the longest names may be drawn.
+        let air_quality: AirQuality =
+            serde_json_core::from_str(r#"{"european_aqi":100,"pollen":[100,100,100,100]}"#)
+                .unwrap()
+                .0;
+
+        for locale in Locale::ALL {
+            let mut canvas = Extent::new(480, 16);
+
+            draw_air_quality(&mut canvas, &air_quality, locale).unwrap();
+
+            assert!(canvas.right < 480, "{locale:?}");
+        }
+
+        // Narrower rows leave out pollen rather than cutting them off.
+        let mut canvas = Extent::new(300, 16);
+
+        draw_air_quality(&mut canvas, &air_quality, Locale::Fr).unwrap();
+
+        assert!(canvas.right < 300);
+    }
+
+    #[test]
+    fn hour_labels_fit_hourly_forecast_column() {
+        let width = |label: &str| text::text_width(SPLEEN_SMALL_STYLE.font, label);
+
+        assert!(width("23:00") <= HOURLY_COLUMN_WIDTH as u32);
+
+        for locale in Locale::ALL {
+            let tomorrow = locale.tomorrow();
+
+            assert!(width(tomorrow) <= HOURLY_COLUMN_WIDTH as u32, "{tomorrow}");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Fitting text into the space of a widget, shared by all widgets.
+
+use core::convert::Infallible;
+
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::prelude::{Drawable, Point};
+use embedded_graphics::text::Text;
+use epd_waveshare::prelude::Color;
+use heapless::String;
+
+use super::{Canvas, TOP_TEXT_STYLE};
+
+/// Capacity of a single shortened line, enough for the width of the display in the smallest font.
+const MAX_LINE_LENGTH: usize = 128;
+
+const ELLIPSIS: &str = "...";
+
+/// How text that is too wide for its space is shortened.
+#[derive(Copy, Clone)]
+pub enum Overflow<'a> {
+    /// Cut off with an ellipsis.
+    Ellipsis,
+    /// Wrapped onto two lines of the given, usually smaller, style with the second one cut off
+    /// with an ellipsis. Both lines together end at the bottom of the unwrapped line.
+    Wrap(MonoTextStyle<'a, Color>),
+}
+
+/// Returns the number of characters of `font` fitting into `width` pixels.
+pub fn fitting_chars(font: &MonoFont<'_>, width: u32) -> usize {
+    let advance = font.character_size.width + font.character_spacing;
+    ((width + font.character_spacing) / advance) as usize
+}
+
+/// Returns the width of `text` in pixels when drawn with `font`.
+pub fn text_width(font: &MonoFont<'_>, text: &str) -> u32 {
+    let advance = font.character_size.width + font.character_spacing;
+    (text.chars().count() as u32 * advance).saturating_sub(font.character_spacing)
+}
+
+/// Splits `s` after at most `max_chars` characters, preferring to break at the last space.
+pub fn split_line(s: &str, max_chars: usize) -> (&str, &str) {
+    let Some((end, _)) = s.char_indices().nth(max_chars) else {
+        return (s, "");
+    };
+
+    let split = s[..end].rfind(' ').unwrap_or(end);
+
+    (&s[..split], s[split..].trim_start())
+}
+
+/// Returns `s` shortened to at most `max_chars` characters, ending in an ellipsis if anything was
+/// cut off.
+pub fn ellipsize(s: &str, max_chars: usize) -> String<MAX_LINE_LENGTH> {
+    let mut line = String::new();
+
+    if s.chars().count() <= max_chars {
+        for c in s.chars() {
+            if line.push(c).is_err() {
+                break;
+            }
+        }
+
+        return line;
+    }
+
+    let kept = max_chars.saturating_sub(ELLIPSIS.len());
+
+    for c in s.chars().take(kept) {
+        if line.push(c).is_err() {
+            break;
+        }
+    }
+
+    let _ = line.push_str(ELLIPSIS);
+    line
+}
+
+/// Draws `text` with its top left corner at `position` into at most `width` pixels, shortening it
+/// according to `overflow` if necessary. Returns the position following the last drawn character.
+pub fn draw_fitted(
+    display: &mut impl Canvas,
+    text: &str,
+    position: Point,
+    width: u32,
+    style: MonoTextStyle<'_, Color>,
+    overflow: Overflow<'_>,
+) -> Result<Point, Infallible> {
+    let max_chars = fitting_chars(style.font, width);
+
+    if text.chars().count() <= max_chars {
+        return Text::with_text_style(text, position, style, TOP_TEXT_STYLE).draw(display);
+    }
+
+    match overflow {
+        Overflow::Ellipsis => {
+            Text::with_text_style(&ellipsize(text, max_chars), position, style, TOP_TEXT_STYLE)
+                .draw(display)
+        }
+        Overflow::Wrap(wrap_style) => {
+            let max_chars = fitting_chars(wrap_style.font, width);
+            let line_height = wrap_style.font.character_size.height as i32;
+            let (first, rest) = split_line(text, max_chars);
+
+            let bottom = position.y + style.font.character_size.height as i32;
+
+            if rest.is_empty() {
+                return Text::with_text_style(
+                    first,
+                    Point::new(position.x, bottom - line_height),
+                    wrap_style,
+                    TOP_TEXT_STYLE,
+                )
+                .draw(display);
+            }
+
+            Text::with_text_style(
+                first,
+                Point::new(position.x, bottom - 2 * line_height),
+                wrap_style,
+                TOP_TEXT_STYLE,
+            )
+            .draw(display)?;
+
+            Text::with_text_style(
+                &ellipsize(rest, max_chars),
+                Point::new(position.x, bottom - line_height),
+                wrap_style,
+                TOP_TEXT_STYLE,
+            )
+            .draw(display)
+        }
+    }
+}
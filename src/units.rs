@@ -0,0 +1,230 @@
+use core::fmt;
+use core::str::FromStr;
+
+use heapless::{String, format};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// Value of Open-Meteo's `temperature_unit` query parameter.
+    pub fn query_value(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// Converts a temperature given in degrees Celsius into this unit.
+    pub fn from_celsius(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindSpeedUnit {
+    KilometresPerHour,
+    MilesPerHour,
+}
+
+impl WindSpeedUnit {
+    /// Value of Open-Meteo's `wind_speed_unit` query parameter.
+    pub fn query_value(self) -> &'static str {
+        match self {
+            WindSpeedUnit::KilometresPerHour => "kmh",
+            WindSpeedUnit::MilesPerHour => "mph",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            WindSpeedUnit::KilometresPerHour => "km/h",
+            WindSpeedUnit::MilesPerHour => "mph",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrecipitationUnit {
+    Millimetres,
+    Inches,
+}
+
+impl PrecipitationUnit {
+    /// Value of Open-Meteo's `precipitation_unit` query parameter.
+    pub fn query_value(self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "inch",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "in",
+        }
+    }
+}
+
+/// Units in which weather data is requested and displayed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind_speed: WindSpeedUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+impl Units {
+    pub const METRIC: Units = Units {
+        temperature: TemperatureUnit::Celsius,
+        wind_speed: WindSpeedUnit::KilometresPerHour,
+        precipitation: PrecipitationUnit::Millimetres,
+    };
+
+    pub const IMPERIAL: Units = Units {
+        temperature: TemperatureUnit::Fahrenheit,
+        wind_speed: WindSpeedUnit::MilesPerHour,
+        precipitation: PrecipitationUnit::Inches,
+    };
+
+    /// Formats a temperature already given in [`Units::temperature`] like
+    /// [`Units::format_temperature_range`], e.g. `-3°C`.
+    pub fn format_temperature(&self, temperature: f32) -> String<8> {
+        format!("{}{}", Rounded(temperature), self.temperature.symbol())
+            .expect("formatting temperature")
+    }
+
+    /// Formats a range of temperatures already given in [`Units::temperature`] without decimals
+    /// and with a single unit symbol, e.g. `-3/5°C`. Temperatures are clamped to three characters,
+    /// so that the range is at most nine characters wide, and unknown ones are shown as `--`.
+    pub fn format_temperature_range(&self, min: f32, max: f32) -> String<16> {
+        format!(
+            "{}/{}{}",
+            Rounded(min),
+            Rounded(max),
+            self.temperature.symbol()
+        )
+        .expect("formatting temperature range")
+    }
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::METRIC
+    }
+}
+
+impl FromStr for Units {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "metric" => Ok(Units::METRIC),
+            "imperial" => Ok(Units::IMPERIAL),
+            _ => Err(Error::Config("unknown units, expected metric or imperial")),
+        }
+    }
+}
+
+/// Displays a temperature without decimals, clamped to three characters, or `--` if it is not a
+/// number or infinite.
+struct Rounded(f32);
+
+impl fmt::Display for Rounded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_finite() {
+            write!(f, "{:.0}", fix_minus_zero(self.0.clamp(-99.0, 999.0)))
+        } else {
+            f.write_str("--")
+        }
+    }
+}
+
+/// Negative values that round to zero, including -0.5 due to rounding half to even, would
+/// otherwise be printed as `-0`.
+fn fix_minus_zero(num: f32) -> f32 {
+    if (-0.5..=0.0).contains(&num) {
+        0.0
+    } else {
+        num
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_temperatures() {
+        assert_eq!(Units::METRIC.format_temperature(-3.4), "-3°C");
+        assert_eq!(Units::METRIC.format_temperature(-0.4), "0°C");
+        assert_eq!(Units::IMPERIAL.format_temperature(75.6), "76°F");
+        assert_eq!(Units::METRIC.format_temperature(-140.0), "-99°C");
+        assert_eq!(Units::IMPERIAL.format_temperature(1200.0), "999°F");
+        assert_eq!(Units::METRIC.format_temperature(f32::MIN), "-99°C");
+        assert_eq!(Units::METRIC.format_temperature(f32::MAX), "999°C");
+        assert_eq!(Units::METRIC.format_temperature(f32::NAN), "--°C");
+        assert_eq!(Units::IMPERIAL.format_temperature(f32::INFINITY), "--°F");
+        assert_eq!(Units::METRIC.format_temperature(f32::NEG_INFINITY), "--°C");
+    }
+
+    #[test]
+    fn formats_temperature_ranges() {
+        assert_eq!(Units::METRIC.format_temperature_range(-3.4, 5.2), "-3/5°C");
+        assert_eq!(Units::METRIC.format_temperature_range(-0.3, 0.4), "0/0°C");
+        assert_eq!(
+            Units::IMPERIAL.format_temperature_range(75.2, 100.4),
+            "75/100°F"
+        );
+        assert_eq!(
+            Units::IMPERIAL.format_temperature_range(-140.0, 1200.0),
+            "-99/999°F"
+        );
+        assert_eq!(
+            Units::METRIC.format_temperature_range(f32::NAN, 5.2),
+            "--/5°C"
+        );
+    }
+
+    #[test]
+    fn temperature_ranges_fit_daily_forecast_column() {
+        // Columns of the daily forecast are 72 px wide, the font is 8 px wide.
+        for units in [Units::METRIC, Units::IMPERIAL] {
+            for (min, max) in [
+                (-99.0, -99.0),
+                (-99.0, 999.0),
+                (-1e9, 1e9),
+                (f32::MIN, f32::MAX),
+                (f32::NAN, f32::INFINITY),
+            ] {
+                let range = units.format_temperature_range(min, max);
+
+                assert!(range.chars().count() * 8 <= 72, "{range}");
+            }
+        }
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!("metric".parse::<Units>().unwrap(), Units::METRIC);
+        assert_eq!("imperial".parse::<Units>().unwrap(), Units::IMPERIAL);
+        assert!("kelvin".parse::<Units>().is_err());
+    }
+}
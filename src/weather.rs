@@ -1,252 +1,472 @@
 use embedded_graphics::image::ImageRaw;
 use embedded_nal_async::{Dns, TcpConnect};
 use epd_waveshare::color::Color;
-use reqwless::{client::HttpClient, request::Method};
-use serde::Deserialize;
-use serde_repr::Deserialize_repr;
-use serde_with::serde_as;
+use reqwless::client::HttpClient;
+use serde::{Deserialize, Serialize};
 
 use crate::errors::Error;
 use crate::icons;
+use crate::units::Units;
 
-const HOURLY_URL: &str = concat!(
-    "https://api.open-meteo.com/v1/forecast?",
-    "latitude=",
-    env!("WEATHER_LAT"),
-    "&longitude=",
-    env!("WEATHER_LON"),
-    "&hourly=temperature_2m,weather_code&timezone=Europe%2FBerlin&forecast_days=2"
-);
-
-const DAILY_URL: &str = concat!(
-    "https://api.open-meteo.com/v1/forecast?",
-    "latitude=",
-    env!("WEATHER_LAT"),
-    "&longitude=",
-    env!("WEATHER_LON"),
-    "&daily=weather_code,temperature_2m_max,temperature_2m_min&timezone=Europe%2FBerlin&forecast_days=4"
-);
-
-#[derive(Debug)]
+mod bright_sky;
+mod met_norway;
+mod open_meteo;
+
+/// Location to fetch weather, alerts and air quality for, in decimal degrees.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+impl Coordinates {
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude) && (-180.0..=180.0).contains(&self.longitude)
+    }
+}
+
+/// Maximum size of a single JSON object in the response arrays of the Bright Sky and MET Norway
+/// APIs.
+const MAX_RECORD_SIZE: usize = 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HourlyForecast {
-    pub hour: u8,
+    pub time: jiff::civil::DateTime,
     pub temperature: f32,
     pub weather_code: WeatherCode,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DailyForecast {
     pub date: jiff::civil::Date,
     pub min_temperature: f32,
     pub max_temperature: f32,
+    /// Minimum "feels like" temperature if reported by the provider.
+    pub apparent_min_temperature: Option<f32>,
+    /// Maximum "feels like" temperature if reported by the provider.
+    pub apparent_max_temperature: Option<f32>,
+    /// Maximum UV index of the day if reported by the provider.
+    pub uv_index_max: Option<f32>,
     pub weather_code: WeatherCode,
 }
 
-#[derive(Deserialize)]
-struct HourlyResponse {
-    hourly: HourlyData,
-}
-
-#[serde_as]
-#[derive(Deserialize)]
-struct HourlyData {
-    #[serde_as(as = "[_; 48]")]
-    temperature_2m: [f32; 48],
-    #[serde_as(as = "[_; 48]")]
-    weather_code: [WeatherCode; 48],
+/// Weather API backends, all producing the same forecast types.
+#[derive(Copy, Clone, Debug)]
+pub enum Provider {
+    /// [Open-Meteo](https://open-meteo.com), worldwide.
+    OpenMeteo,
+    /// [Bright Sky](https://brightsky.dev) serving DWD open data, Germany only.
+    BrightSky,
+    /// [MET Norway Locationforecast](https://api.met.no), worldwide.
+    MetNorway,
 }
 
-#[derive(Deserialize)]
-struct DailyResponse {
-    daily: DailyData,
-}
+impl Provider {
+    /// Fetch `HOURS` hourly forecasts starting at midnight or the current hour, with temperatures
+    /// in `units`.
+    pub async fn hourly_forecast<const HOURS: usize, T, D>(
+        self,
+        client: &mut HttpClient<'_, T, D>,
+        coordinates: Coordinates,
+        now: &jiff::Zoned,
+        units: &Units,
+    ) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error>
+    where
+        T: TcpConnect,
+        D: Dns,
+    {
+        match self {
+            Provider::OpenMeteo => open_meteo::hourly_forecast(client, coordinates, units).await,
+            Provider::BrightSky => {
+                bright_sky::hourly_forecast(client, coordinates, now, units).await
+            }
+            Provider::MetNorway => {
+                met_norway::hourly_forecast(client, coordinates, now, units).await
+            }
+        }
+    }
 
-#[serde_as]
-#[derive(Deserialize)]
-struct DailyData {
-    time: [jiff::civil::Date; 4],
-    temperature_2m_max: [f32; 4],
-    temperature_2m_min: [f32; 4],
-    weather_code: [WeatherCode; 4],
+    /// Fetch `DAYS` daily forecasts starting today, with temperatures in `units`.
+    pub async fn daily_forecast<const DAYS: usize, T, D>(
+        self,
+        client: &mut HttpClient<'_, T, D>,
+        coordinates: Coordinates,
+        now: &jiff::Zoned,
+        units: &Units,
+    ) -> Result<heapless::Vec<DailyForecast, DAYS>, Error>
+    where
+        T: TcpConnect,
+        D: Dns,
+    {
+        match self {
+            Provider::OpenMeteo => open_meteo::daily_forecast(client, coordinates, units).await,
+            Provider::BrightSky => {
+                bright_sky::daily_forecast(client, coordinates, now, units).await
+            }
+            Provider::MetNorway => {
+                met_norway::daily_forecast(client, coordinates, now, units).await
+            }
+        }
+    }
 }
 
-pub async fn hourly_forecast<T, D>(
+/// Fetch `HOURS` hourly forecasts starting at midnight or the current hour from the first of
+/// `providers` that succeeds.
+pub async fn hourly_forecast<const HOURS: usize, T, D>(
     client: &mut HttpClient<'_, T, D>,
-) -> Result<heapless::Vec<HourlyForecast, 48>, Error>
+    providers: &[Provider],
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error>
 where
     T: TcpConnect,
     D: Dns,
 {
-    log::debug!("getting hourly forecast");
+    let mut result = Err(Error::Http("no weather provider configured"));
 
-    let mut write_buffer = [0u8; 1024];
-    let mut read_buffer = [0u8; 4096 + 2048];
+    for provider in providers {
+        log::debug!("getting hourly forecast from {provider:?}");
 
-    let bytes_read = get(client, HOURLY_URL, &mut write_buffer, &mut read_buffer).await?;
+        result = provider
+            .hourly_forecast(client, coordinates, now, units)
+            .await;
 
-    let (response, _) = serde_json_core::from_slice::<HourlyResponse>(&read_buffer[..bytes_read])
-        .map_err(|_| Error::ParseJson("failed to parse hourly response"))?;
-
-    let forecast = response
-        .hourly
-        .temperature_2m
-        .into_iter()
-        .zip(response.hourly.weather_code)
-        .enumerate()
-        .map(|(index, (temperature, weather_code))| HourlyForecast {
-            hour: index as u8 % 24,
-            temperature,
-            weather_code,
-        })
-        .collect();
+        match &result {
+            Ok(_) => break,
+            Err(err) => log::warn!("hourly forecast from {provider:?} failed: {err:?}"),
+        }
+    }
 
-    Ok(forecast)
+    result
 }
 
-pub async fn daily_forecast<T, D>(
-    client: &mut HttpClient<'_, T, D>,
-) -> Result<heapless::Vec<DailyForecast, 4>, Error>
-where
-    T: TcpConnect,
-    D: Dns,
-{
-    log::debug!("getting daily forecast");
-
-    let mut write_buffer = [0u8; 1024];
-    let mut read_buffer = [0u8; 4096];
-
-    let bytes_read = get(client, DAILY_URL, &mut write_buffer, &mut read_buffer).await?;
-
-    let (response, _) = serde_json_core::from_slice::<DailyResponse>(&read_buffer[..bytes_read])
-        .map_err(|_| Error::ParseJson("failed to parse daily response"))?;
-
-    let DailyData {
-        time: date,
-        temperature_2m_min: min_temperature,
-        temperature_2m_max: max_temperature,
-        weather_code,
-    } = response.daily;
-
-    let forecast = date
-        .into_iter()
-        .zip(min_temperature)
-        .zip(max_temperature)
-        .zip(weather_code)
-        .map(
-            |(((date, min_temperature), max_temperature), weather_code)| DailyForecast {
-                date,
-                min_temperature,
-                max_temperature,
-                weather_code,
-            },
-        )
-        .collect();
-
-    Ok(forecast)
-}
-
-async fn get<T, D>(
+/// Fetch `DAYS` daily forecasts starting today from the first of `providers` that succeeds.
+pub async fn daily_forecast<const DAYS: usize, T, D>(
     client: &mut HttpClient<'_, T, D>,
-    url: &str,
-    write_buffer: &mut [u8],
-    read_buffer: &mut [u8],
-) -> Result<usize, Error>
+    providers: &[Provider],
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error>
 where
     T: TcpConnect,
     D: Dns,
 {
-    let size = client
-        .request(Method::GET, url)
-        .await
-        .map_err(|_| Error::Http("failed to connect to weather URL"))?
-        .send(write_buffer)
-        .await
-        .map_err(|_| Error::Http("failed to send request"))?
-        .body()
-        .reader()
-        .read_to_end(read_buffer)
-        .await
-        .map_err(|_| Error::Http("failed to read into buffer"))?;
-
-    Ok(size)
+    let mut result = Err(Error::Http("no weather provider configured"));
+
+    for provider in providers {
+        log::debug!("getting daily forecast from {provider:?}");
+
+        result = provider
+            .daily_forecast(client, coordinates, now, units)
+            .await;
+
+        match &result {
+            Ok(_) => break,
+            Err(err) => log::warn!("daily forecast from {provider:?} failed: {err:?}"),
+        }
+    }
+
+    result
+}
+
+/// Accumulates hourly samples into per-day forecasts.
+struct Days<const N: usize> {
+    days: heapless::Vec<DailyForecast, N>,
+}
+
+impl<const N: usize> Days<N> {
+    fn new() -> Self {
+        Self {
+            days: heapless::Vec::new(),
+        }
+    }
+
+    /// Add a sample to its day. Returns `false` once a sample for day `N + 1` arrives.
+    fn add(
+        &mut self,
+        date: jiff::civil::Date,
+        temperature: f32,
+        weather_code: WeatherCode,
+    ) -> bool {
+        match self.days.last_mut() {
+            Some(day) if day.date == date => {
+                day.min_temperature = day.min_temperature.min(temperature);
+                day.max_temperature = day.max_temperature.max(temperature);
+
+                // The worst weather of the day wins.
+                if weather_code.severity() > day.weather_code.severity() {
+                    day.weather_code = weather_code;
+                }
+
+                true
+            }
+            _ => self
+                .days
+                .push(DailyForecast {
+                    date,
+                    min_temperature: temperature,
+                    max_temperature: temperature,
+                    apparent_min_temperature: None,
+                    apparent_max_temperature: None,
+                    uv_index_max: None,
+                    weather_code,
+                })
+                .is_ok(),
+        }
+    }
+}
+
+/// Returns `true` if `hour` should be drawn with a day rather than a night icon.
+fn is_day(hour: u8) -> bool {
+    (8..=19).contains(&hour)
 }
 
 pub fn hourly_icon(hour: u8, weather_code: WeatherCode) -> &'static ImageRaw<'static, Color> {
-    match (hour, weather_code) {
-        (8..=19, WeatherCode::Clear | WeatherCode::MainlyClear) => &icons::SUN,
-        (8..=19, WeatherCode::PartlyCloudy) => &icons::CLOUD_SUN,
-        (0..=7 | 20..=23, WeatherCode::Clear | WeatherCode::MainlyClear) => &icons::MOON,
-        (0..=7 | 20..=23, WeatherCode::PartlyCloudy) => &icons::CLOUD_MOON,
-        (_, WeatherCode::Overcast) => &icons::CLOUD,
-        (_, WeatherCode::Fog) => &icons::CLOUD_WIND,
-        (_, WeatherCode::SlightRain | WeatherCode::LightDrizzle | WeatherCode::ModerateDrizzle) => {
-            &icons::RAIN0
+    let day = is_day(hour);
+
+    match weather_code {
+        WeatherCode::Clear | WeatherCode::MainlyClear if day => &icons::SUN,
+        WeatherCode::Clear | WeatherCode::MainlyClear => &icons::MOON,
+        WeatherCode::PartlyCloudy if day => &icons::CLOUD_SUN,
+        WeatherCode::PartlyCloudy => &icons::CLOUD_MOON,
+        WeatherCode::Overcast => &icons::CLOUDS,
+        WeatherCode::Fog | WeatherCode::DepositingRimeFog => &icons::CLOUD_WIND,
+        WeatherCode::LightDrizzle
+        | WeatherCode::ModerateDrizzle
+        | WeatherCode::DenseDrizzle
+        | WeatherCode::SlightRain => &icons::RAIN0,
+        WeatherCode::ModerateRain => &icons::RAIN1,
+        WeatherCode::HeavyRain | WeatherCode::ViolentRainShower => &icons::RAIN2,
+        WeatherCode::SlightRainShower if day => &icons::RAIN0_SUN,
+        WeatherCode::ModerateRainShower if day => &icons::RAIN1_SUN,
+        WeatherCode::SlightRainShower | WeatherCode::ModerateRainShower => &icons::RAIN1_MOON,
+        WeatherCode::LightFreezingDrizzle
+        | WeatherCode::DenseFreezingDrizzle
+        | WeatherCode::FreezingLightRain
+        | WeatherCode::FreezingHeavyRain => &icons::RAIN_SNOW,
+        WeatherCode::SlightSnow
+        | WeatherCode::ModerateSnow
+        | WeatherCode::HeavySnow
+        | WeatherCode::SnowGrains => &icons::SNOW,
+        WeatherCode::SlightSnowShower | WeatherCode::HeavySnowShower if day => &icons::SNOW_SUN,
+        WeatherCode::SlightSnowShower | WeatherCode::HeavySnowShower => &icons::SNOW_MOON,
+        WeatherCode::SlightThunderstorm => &icons::LIGHTNING,
+        WeatherCode::SlightThunderstormSlightHail | WeatherCode::SlightThunderstormHeavyHail => {
+            &icons::RAIN_LIGHTNING
         }
-        (_, WeatherCode::ModerateRain | WeatherCode::SlightRainShower) => &icons::RAIN1,
-        (
-            _,
-            WeatherCode::HeavyRain
-            | WeatherCode::ModerateRainShower
-            | WeatherCode::ViolentRainShower,
-        ) => &icons::RAIN2,
-        (
-            _,
-            WeatherCode::SlightSnow
-            | WeatherCode::ModerateSnow
-            | WeatherCode::HeavySnow
-            | WeatherCode::SnowGrains
-            | WeatherCode::SlightSnowShower
-            | WeatherCode::HeavySnowShower,
-        ) => &icons::SNOW,
-        (
-            _,
-            WeatherCode::FreezingLightRain
-            | WeatherCode::FreezingHeavyRain
-            | WeatherCode::LightFreezingDrizzle
-            | WeatherCode::DenseFreezingDrizzle,
-        ) => &icons::RAIN_SNOW,
-        (
-            _,
-            WeatherCode::SlightThunderstorm
-            | WeatherCode::SlightThunderstormSlightHail
-            | WeatherCode::SlightThunderstormHeavyHail,
-        ) => &icons::LIGHTNING,
-        x => {
-            log::warn!("{x:?} not covered");
-            &icons::SUN
+        WeatherCode::Unknown(code) => {
+            log::warn!("unknown weather code {code}");
+            &icons::CLOUD
         }
     }
 }
 
-#[derive(Copy, Clone, Deserialize_repr, Debug)]
-#[repr(u8)]
+/// WMO weather interpretation codes as returned by Open-Meteo.
+///
+/// Codes we do not know about are kept as [`WeatherCode::Unknown`] instead of failing to parse the
+/// whole response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
 pub enum WeatherCode {
-    Clear = 0,
-    MainlyClear = 1,
-    PartlyCloudy = 2,
-    Overcast = 3,
-    Fog = 45,
-    DepositingRimeFog = 48,
-    LightDrizzle = 51,
-    ModerateDrizzle = 53,
-    DenseDrizzle = 55,
-    LightFreezingDrizzle = 56,
-    DenseFreezingDrizzle = 57,
-    SlightRain = 61,
-    ModerateRain = 63,
-    HeavyRain = 65,
-    FreezingLightRain = 66,
-    FreezingHeavyRain = 67,
-    SlightSnow = 71,
-    ModerateSnow = 73,
-    HeavySnow = 75,
-    SnowGrains = 77,
-    SlightRainShower = 80,
-    ModerateRainShower = 81,
-    ViolentRainShower = 82,
-    SlightSnowShower = 85,
-    HeavySnowShower = 86,
-    SlightThunderstorm = 95,
-    SlightThunderstormSlightHail = 96,
-    SlightThunderstormHeavyHail = 99,
+    Clear,
+    MainlyClear,
+    PartlyCloudy,
+    Overcast,
+    Fog,
+    DepositingRimeFog,
+    LightDrizzle,
+    ModerateDrizzle,
+    DenseDrizzle,
+    LightFreezingDrizzle,
+    DenseFreezingDrizzle,
+    SlightRain,
+    ModerateRain,
+    HeavyRain,
+    FreezingLightRain,
+    FreezingHeavyRain,
+    SlightSnow,
+    ModerateSnow,
+    HeavySnow,
+    SnowGrains,
+    SlightRainShower,
+    ModerateRainShower,
+    ViolentRainShower,
+    SlightSnowShower,
+    HeavySnowShower,
+    SlightThunderstorm,
+    SlightThunderstormSlightHail,
+    SlightThunderstormHeavyHail,
+    Unknown(u8),
+}
+
+impl WeatherCode {
+    /// Rank used to pick the most significant weather of a period. WMO codes grow with severity,
+    /// unknown codes rank lowest.
+    fn severity(self) -> u8 {
+        match self {
+            WeatherCode::Unknown(_) => 0,
+            code => code.into(),
+        }
+    }
+}
+
+impl From<u8> for WeatherCode {
+    fn from(code: u8) -> Self {
+        match code {
+            0 => Self::Clear,
+            1 => Self::MainlyClear,
+            2 => Self::PartlyCloudy,
+            3 => Self::Overcast,
+            45 => Self::Fog,
+            48 => Self::DepositingRimeFog,
+            51 => Self::LightDrizzle,
+            53 => Self::ModerateDrizzle,
+            55 => Self::DenseDrizzle,
+            56 => Self::LightFreezingDrizzle,
+            57 => Self::DenseFreezingDrizzle,
+            61 => Self::SlightRain,
+            63 => Self::ModerateRain,
+            65 => Self::HeavyRain,
+            66 => Self::FreezingLightRain,
+            67 => Self::FreezingHeavyRain,
+            71 => Self::SlightSnow,
+            73 => Self::ModerateSnow,
+            75 => Self::HeavySnow,
+            77 => Self::SnowGrains,
+            80 => Self::SlightRainShower,
+            81 => Self::ModerateRainShower,
+            82 => Self::ViolentRainShower,
+            85 => Self::SlightSnowShower,
+            86 => Self::HeavySnowShower,
+            95 => Self::SlightThunderstorm,
+            96 => Self::SlightThunderstormSlightHail,
+            99 => Self::SlightThunderstormHeavyHail,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<WeatherCode> for u8 {
+    fn from(code: WeatherCode) -> Self {
+        match code {
+            WeatherCode::Clear => 0,
+            WeatherCode::MainlyClear => 1,
+            WeatherCode::PartlyCloudy => 2,
+            WeatherCode::Overcast => 3,
+            WeatherCode::Fog => 45,
+            WeatherCode::DepositingRimeFog => 48,
+            WeatherCode::LightDrizzle => 51,
+            WeatherCode::ModerateDrizzle => 53,
+            WeatherCode::DenseDrizzle => 55,
+            WeatherCode::LightFreezingDrizzle => 56,
+            WeatherCode::DenseFreezingDrizzle => 57,
+            WeatherCode::SlightRain => 61,
+            WeatherCode::ModerateRain => 63,
+            WeatherCode::HeavyRain => 65,
+            WeatherCode::FreezingLightRain => 66,
+            WeatherCode::FreezingHeavyRain => 67,
+            WeatherCode::SlightSnow => 71,
+            WeatherCode::ModerateSnow => 73,
+            WeatherCode::HeavySnow => 75,
+            WeatherCode::SnowGrains => 77,
+            WeatherCode::SlightRainShower => 80,
+            WeatherCode::ModerateRainShower => 81,
+            WeatherCode::ViolentRainShower => 82,
+            WeatherCode::SlightSnowShower => 85,
+            WeatherCode::HeavySnowShower => 86,
+            WeatherCode::SlightThunderstorm => 95,
+            WeatherCode::SlightThunderstormSlightHail => 96,
+            WeatherCode::SlightThunderstormHeavyHail => 99,
+            WeatherCode::Unknown(code) => code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_weather_codes() {
+        let mut known = 0;
+
+        for code in 0..=u8::MAX {
+            let weather_code = WeatherCode::from(code);
+
+            assert_eq!(u8::from(weather_code), code);
+
+            if weather_code != WeatherCode::Unknown(code) {
+                known += 1;
+            }
+        }
+
+        assert_eq!(known, 28);
+        assert_eq!(WeatherCode::from(4), WeatherCode::Unknown(4));
+        assert_eq!(WeatherCode::from(u8::MAX), WeatherCode::Unknown(u8::MAX));
+    }
+
+    #[test]
+    fn picks_hourly_icons_for_day_and_night() {
+        use WeatherCode::*;
+
+        let icons: [(WeatherCode, &ImageRaw<Color>, &ImageRaw<Color>); 28] = [
+            (Clear, &icons::SUN, &icons::MOON),
+            (MainlyClear, &icons::SUN, &icons::MOON),
+            (PartlyCloudy, &icons::CLOUD_SUN, &icons::CLOUD_MOON),
+            (Overcast, &icons::CLOUDS, &icons::CLOUDS),
+            (Fog, &icons::CLOUD_WIND, &icons::CLOUD_WIND),
+            (DepositingRimeFog, &icons::CLOUD_WIND, &icons::CLOUD_WIND),
+            (LightDrizzle, &icons::RAIN0, &icons::RAIN0),
+            (ModerateDrizzle, &icons::RAIN0, &icons::RAIN0),
+            (DenseDrizzle, &icons::RAIN0, &icons::RAIN0),
+            (LightFreezingDrizzle, &icons::RAIN_SNOW, &icons::RAIN_SNOW),
+            (DenseFreezingDrizzle, &icons::RAIN_SNOW, &icons::RAIN_SNOW),
+            (SlightRain, &icons::RAIN0, &icons::RAIN0),
+            (ModerateRain, &icons::RAIN1, &icons::RAIN1),
+            (HeavyRain, &icons::RAIN2, &icons::RAIN2),
+            (FreezingLightRain, &icons::RAIN_SNOW, &icons::RAIN_SNOW),
+            (FreezingHeavyRain, &icons::RAIN_SNOW, &icons::RAIN_SNOW),
+            (SlightSnow, &icons::SNOW, &icons::SNOW),
+            (ModerateSnow, &icons::SNOW, &icons::SNOW),
+            (HeavySnow, &icons::SNOW, &icons::SNOW),
+            (SnowGrains, &icons::SNOW, &icons::SNOW),
+            (SlightRainShower, &icons::RAIN0_SUN, &icons::RAIN1_MOON),
+            (ModerateRainShower, &icons::RAIN1_SUN, &icons::RAIN1_MOON),
+            (ViolentRainShower, &icons::RAIN2, &icons::RAIN2),
+            (SlightSnowShower, &icons::SNOW_SUN, &icons::SNOW_MOON),
+            (HeavySnowShower, &icons::SNOW_SUN, &icons::SNOW_MOON),
+            (SlightThunderstorm, &icons::LIGHTNING, &icons::LIGHTNING),
+            (
+                SlightThunderstormSlightHail,
+                &icons::RAIN_LIGHTNING,
+                &icons::RAIN_LIGHTNING,
+            ),
+            (
+                SlightThunderstormHeavyHail,
+                &icons::RAIN_LIGHTNING,
+                &icons::RAIN_LIGHTNING,
+            ),
+        ];
+
+        for code in 0..=u8::MAX {
+            let weather_code = WeatherCode::from(code);
+
+            let (day, night) = icons
+                .iter()
+                .find(|(known, _, _)| *known == weather_code)
+                .map_or((&icons::CLOUD, &icons::CLOUD), |&(_, day, night)| {
+                    (day, night)
+                });
+
+            for hour in 8..=19 {
+                assert!(hourly_icon(hour, weather_code) == day, "{code} at {hour}");
+            }
+
+            for hour in (0..8).chain(20..24) {
+                assert!(hourly_icon(hour, weather_code) == night, "{code} at {hour}");
+            }
+        }
+    }
 }
@@ -0,0 +1,398 @@
+//! Forecasts from [Bright Sky](https://brightsky.dev), a JSON API for the open data of the Deutscher
+//! Wetterdienst (DWD). Bright Sky only provides hourly records, so daily forecasts are aggregated
+//! from them. Bright Sky always reports degrees Celsius.
+
+use embedded_io_async::Read;
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::{String, format};
+use jiff::tz::TimeZone;
+use reqwless::{client::HttpClient, request::Method};
+use serde::Deserialize;
+
+use super::{Coordinates, DailyForecast, Days, HourlyForecast, MAX_RECORD_SIZE, WeatherCode};
+use crate::errors::Error;
+use crate::json::Tokenizer;
+use crate::units::Units;
+
+#[derive(Deserialize)]
+struct Record<'a> {
+    timestamp: jiff::Timestamp,
+    temperature: Option<f32>,
+    precipitation: Option<f32>,
+    #[serde(borrow)]
+    icon: Option<&'a str>,
+    #[serde(borrow)]
+    condition: Option<&'a str>,
+}
+
+impl Record<'_> {
+    fn weather_code(&self) -> WeatherCode {
+        let precipitation = self.precipitation.unwrap_or_default();
+
+        match self.icon.or(self.condition) {
+            Some("clear-day" | "clear-night" | "dry") => WeatherCode::Clear,
+            Some("partly-cloudy-day" | "partly-cloudy-night") => WeatherCode::PartlyCloudy,
+            Some("cloudy" | "wind") => WeatherCode::Overcast,
+            Some("fog") => WeatherCode::Fog,
+            Some("rain") if precipitation < 0.5 => WeatherCode::SlightRain,
+            Some("rain") if precipitation < 4.0 => WeatherCode::ModerateRain,
+            Some("rain") => WeatherCode::HeavyRain,
+            Some("sleet") if precipitation < 1.0 => WeatherCode::FreezingLightRain,
+            Some("sleet") => WeatherCode::FreezingHeavyRain,
+            Some("snow") if precipitation < 0.5 => WeatherCode::SlightSnow,
+            Some("snow") if precipitation < 2.0 => WeatherCode::ModerateSnow,
+            Some("snow") => WeatherCode::HeavySnow,
+            Some("hail") => WeatherCode::SlightThunderstormSlightHail,
+            Some("thunderstorm") => WeatherCode::SlightThunderstorm,
+            other => {
+                log::warn!("unknown Bright Sky condition {other:?}");
+                WeatherCode::Unknown(u8::MAX)
+            }
+        }
+    }
+}
+
+pub(super) async fn hourly_forecast<const HOURS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url = url(coordinates, now, HOURS.div_ceil(24) as i64)?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_hourly(response.body().reader(), now.time_zone(), units).await
+}
+
+pub(super) async fn daily_forecast<const DAYS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url = url(coordinates, now, DAYS as i64)?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_daily(response.body().reader(), now.time_zone(), units).await
+}
+
+/// Returns the URL of `days` worth of hourly records starting today.
+fn url(coordinates: Coordinates, now: &jiff::Zoned, days: i64) -> Result<String<192>, Error> {
+    let today = now.date();
+    let last_date = today
+        .checked_add(jiff::Span::new().days(days))
+        .map_err(|_| Error::DateTime("failed to compute last forecast date"))?;
+
+    format!(
+        "https://api.brightsky.dev/weather?lat={:.4}&lon={:.4}&date={today}&last_date={last_date}&tz=Europe%2FBerlin",
+        coordinates.latitude,
+        coordinates.longitude
+    )
+    .map_err(|_| Error::Http("weather URL too long"))
+}
+
+async fn parse_hourly<const HOURS: usize, R: Read>(
+    reader: R,
+    timezone: &TimeZone,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error> {
+    let mut forecast = heapless::Vec::new();
+
+    records(reader, |record| {
+        let Some(temperature) = record.temperature else {
+            return true;
+        };
+
+        forecast
+            .push(HourlyForecast {
+                time: record.timestamp.to_zoned(timezone.clone()).datetime(),
+                temperature: units.temperature.from_celsius(temperature),
+                weather_code: record.weather_code(),
+            })
+            .is_ok()
+    })
+    .await?;
+
+    Ok(forecast)
+}
+
+async fn parse_daily<const DAYS: usize, R: Read>(
+    reader: R,
+    timezone: &TimeZone,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error> {
+    let mut days = Days::new();
+
+    records(reader, |record| {
+        let Some(temperature) = record.temperature else {
+            return true;
+        };
+
+        let date = record.timestamp.to_zoned(timezone.clone()).date();
+        days.add(
+            date,
+            units.temperature.from_celsius(temperature),
+            record.weather_code(),
+        )
+    })
+    .await?;
+
+    Ok(days.days)
+}
+
+/// Pass the hourly records of the response read from `reader` to `f` until it returns `false`.
+async fn records<R, F>(reader: R, mut f: F) -> Result<(), Error>
+where
+    R: Read,
+    F: FnMut(Record<'_>) -> bool,
+{
+    let mut record_buffer = [0u8; MAX_RECORD_SIZE];
+
+    let mut json = Tokenizer::<_>::new(reader);
+    json.seek_key("weather").await?;
+    json.begin_array().await?;
+
+    while let Some(bytes) = json.raw_value(&mut record_buffer).await? {
+        let (record, _) = serde_json_core::from_slice::<Record>(bytes)
+            .map_err(|_| Error::ParseJson("failed to parse Bright Sky record"))?;
+
+        if !f(record) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &[u8] = include_bytes!("../../fixtures/bright_sky.json");
+
+    fn timezone() -> TimeZone {
+        TimeZone::fixed(jiff::tz::offset(1))
+    }
+
+    #[test]
+    fn parses_hourly_forecast() {
+        let forecast =
+            embassy_futures::block_on(parse_hourly::<8, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+        let time = |day, hour| jiff::civil::date(2025, 1, day).at(hour, 0, 0, 0);
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| (forecast.time, forecast.temperature, forecast.weather_code))
+            .collect();
+
+        // The record without temperature is skipped.
+        assert_eq!(
+            forecast,
+            [
+                (time(15, 22), -0.4, WeatherCode::Overcast),
+                (time(15, 23), -1.1, WeatherCode::Clear),
+                (time(16, 1), -1.8, WeatherCode::SlightSnow),
+                (time(16, 2), 0.6, WeatherCode::FreezingHeavyRain),
+                (time(16, 3), 1.2, WeatherCode::HeavyRain),
+                (time(17, 0), 2.5, WeatherCode::PartlyCloudy),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_temperatures() {
+        let forecast = embassy_futures::block_on(parse_hourly::<2, _>(
+            RESPONSE,
+            &timezone(),
+            &Units::IMPERIAL,
+        ))
+        .unwrap();
+
+        assert_eq!(forecast.len(), 2);
+        assert!((forecast[0].temperature - 31.28).abs() < 0.001);
+        assert!((forecast[1].temperature - 30.02).abs() < 0.001);
+    }
+
+    #[test]
+    fn aggregates_daily_forecast() {
+        let forecast =
+            embassy_futures::block_on(parse_daily::<3, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| {
+                (
+                    forecast.date,
+                    forecast.min_temperature,
+                    forecast.max_temperature,
+                    forecast.weather_code,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            forecast,
+            [
+                (
+                    jiff::civil::date(2025, 1, 15),
+                    -1.1,
+                    -0.4,
+                    WeatherCode::Overcast
+                ),
+                (
+                    jiff::civil::date(2025, 1, 16),
+                    -1.8,
+                    1.2,
+                    WeatherCode::SlightSnow
+                ),
+                (
+                    jiff::civil::date(2025, 1, 17),
+                    2.5,
+                    2.5,
+                    WeatherCode::PartlyCloudy
+                ),
+            ]
+        );
+
+        let forecast =
+            embassy_futures::block_on(parse_daily::<2, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+
+        assert_eq!(forecast.len(), 2);
+    }
+
+    #[test]
+    fn maps_conditions_to_weather_codes() {
+        let record = |icon, condition, precipitation| Record {
+            timestamp: jiff::Timestamp::UNIX_EPOCH,
+            temperature: None,
+            precipitation,
+            icon,
+            condition,
+        };
+
+        for (icon, condition, precipitation, weather_code) in [
+            (Some("clear-day"), None, None, WeatherCode::Clear),
+            (Some("clear-night"), Some("dry"), None, WeatherCode::Clear),
+            (None, Some("dry"), None, WeatherCode::Clear),
+            (
+                Some("partly-cloudy-day"),
+                None,
+                None,
+                WeatherCode::PartlyCloudy,
+            ),
+            (
+                Some("partly-cloudy-night"),
+                None,
+                None,
+                WeatherCode::PartlyCloudy,
+            ),
+            (Some("cloudy"), None, None, WeatherCode::Overcast),
+            (Some("wind"), None, None, WeatherCode::Overcast),
+            (Some("fog"), None, None, WeatherCode::Fog),
+            (Some("rain"), None, None, WeatherCode::SlightRain),
+            (Some("rain"), None, Some(0.4), WeatherCode::SlightRain),
+            (Some("rain"), None, Some(0.5), WeatherCode::ModerateRain),
+            (Some("rain"), None, Some(4.0), WeatherCode::HeavyRain),
+            (
+                Some("sleet"),
+                None,
+                Some(0.9),
+                WeatherCode::FreezingLightRain,
+            ),
+            (
+                Some("sleet"),
+                None,
+                Some(1.0),
+                WeatherCode::FreezingHeavyRain,
+            ),
+            (Some("snow"), None, Some(0.4), WeatherCode::SlightSnow),
+            (Some("snow"), None, Some(0.5), WeatherCode::ModerateSnow),
+            (Some("snow"), None, Some(2.0), WeatherCode::HeavySnow),
+            (
+                Some("hail"),
+                None,
+                None,
+                WeatherCode::SlightThunderstormSlightHail,
+            ),
+            (
+                Some("thunderstorm"),
+                None,
+                None,
+                WeatherCode::SlightThunderstorm,
+            ),
+            (
+                None,
+                Some("thunderstorm"),
+                None,
+                WeatherCode::SlightThunderstorm,
+            ),
+            (None, None, None, WeatherCode::Unknown(u8::MAX)),
+            (Some("tornado"), None, None, WeatherCode::Unknown(u8::MAX)),
+        ] {
+            assert_eq!(
+                record(icon, condition, precipitation).weather_code(),
+                weather_code,
+                "{icon:?} {condition:?} {precipitation:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_records_exceeding_buffer() {
+        let record = |padding| {
+            std::format!(
+                "{{\"timestamp\":\"2025-01-15T22:00:00+01:00\",\"temperature\":1.0,\"icon\":\"fog\",\"padding\":\"{}\"}}",
+                "x".repeat(padding)
+            )
+        };
+        let parse = |record: &str| {
+            let response = std::format!("{{\"weather\":[{record}]}}");
+
+            embassy_futures::block_on(parse_hourly::<8, _>(
+                response.as_bytes(),
+                &timezone(),
+                &Units::METRIC,
+            ))
+        };
+
+        let fitting = record(MAX_RECORD_SIZE - record(0).len());
+        assert_eq!(fitting.len(), MAX_RECORD_SIZE);
+        assert_eq!(parse(&fitting).unwrap().len(), 1);
+
+        let exceeding = record(MAX_RECORD_SIZE + 1 - record(0).len());
+        assert!(parse(&exceeding).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Forecasts from the [MET Norway Locationforecast](https://api.met.no/weatherapi/locationforecast/2.0/documentation)
+//! API. Time steps start at the current hour, are hourly for roughly the next two days and six
+//! hourly afterwards. Daily forecasts are aggregated from them. Temperatures are always reported
+//! in degrees Celsius.
+
+use embedded_io_async::Read;
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::{String, format};
+use jiff::tz::TimeZone;
+use reqwless::{client::HttpClient, request::Method};
+use serde::Deserialize;
+
+use super::{Coordinates, DailyForecast, Days, HourlyForecast, MAX_RECORD_SIZE, WeatherCode};
+use crate::errors::Error;
+use crate::json::Tokenizer;
+use crate::units::Units;
+
+/// MET Norway's terms of service require an identifying user agent.
+const USER_AGENT: &str = "esp-home-dashboard github.com/matze/esp-home-dashboard";
+
+#[derive(Deserialize)]
+struct TimeStep<'a> {
+    time: jiff::Timestamp,
+    #[serde(borrow)]
+    data: Data<'a>,
+}
+
+#[derive(Deserialize)]
+struct Data<'a> {
+    instant: Instant,
+    #[serde(borrow)]
+    next_1_hours: Option<Period<'a>>,
+    #[serde(borrow)]
+    next_6_hours: Option<Period<'a>>,
+}
+
+#[derive(Deserialize)]
+struct Instant {
+    details: InstantDetails,
+}
+
+#[derive(Deserialize)]
+struct InstantDetails {
+    air_temperature: f32,
+}
+
+#[derive(Deserialize)]
+struct Period<'a> {
+    #[serde(borrow)]
+    summary: Summary<'a>,
+}
+
+#[derive(Deserialize)]
+struct Summary<'a> {
+    symbol_code: &'a str,
+}
+
+impl TimeStep<'_> {
+    /// Returns the weather code of the shortest period following this time step.
+    fn weather_code(&self) -> Option<WeatherCode> {
+        self.data
+            .next_1_hours
+            .as_ref()
+            .or(self.data.next_6_hours.as_ref())
+            .map(|period| weather_code(period.summary.symbol_code))
+    }
+}
+
+/// Translate a MET Norway symbol code such as `lightrainshowers_day` into a [`WeatherCode`].
+fn weather_code(symbol_code: &str) -> WeatherCode {
+    let symbol = symbol_code
+        .split_once('_')
+        .map_or(symbol_code, |(symbol, _)| symbol);
+
+    match symbol {
+        "clearsky" => WeatherCode::Clear,
+        "fair" => WeatherCode::MainlyClear,
+        "partlycloudy" => WeatherCode::PartlyCloudy,
+        "cloudy" => WeatherCode::Overcast,
+        "fog" => WeatherCode::Fog,
+        "lightrain" => WeatherCode::SlightRain,
+        "rain" => WeatherCode::ModerateRain,
+        "heavyrain" => WeatherCode::HeavyRain,
+        "lightrainshowers" => WeatherCode::SlightRainShower,
+        "rainshowers" => WeatherCode::ModerateRainShower,
+        "heavyrainshowers" => WeatherCode::ViolentRainShower,
+        "lightsleet" | "sleet" | "lightsleetshowers" | "sleetshowers" => {
+            WeatherCode::FreezingLightRain
+        }
+        "heavysleet" | "heavysleetshowers" => WeatherCode::FreezingHeavyRain,
+        "lightsnow" => WeatherCode::SlightSnow,
+        "snow" => WeatherCode::ModerateSnow,
+        "heavysnow" => WeatherCode::HeavySnow,
+        "lightsnowshowers" | "snowshowers" => WeatherCode::SlightSnowShower,
+        "heavysnowshowers" => WeatherCode::HeavySnowShower,
+        symbol if symbol.ends_with("andthunder") => WeatherCode::SlightThunderstorm,
+        _ => {
+            log::warn!("unknown MET Norway symbol {symbol_code}");
+            WeatherCode::Unknown(u8::MAX)
+        }
+    }
+}
+
+pub(super) async fn hourly_forecast<const HOURS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url = url(coordinates)?;
+    let headers = [("User-Agent", USER_AGENT)];
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?
+        .headers(&headers);
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_hourly(response.body().reader(), now.time_zone(), units).await
+}
+
+pub(super) async fn daily_forecast<const DAYS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    now: &jiff::Zoned,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url = url(coordinates)?;
+    let headers = [("User-Agent", USER_AGENT)];
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?
+        .headers(&headers);
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_daily(response.body().reader(), now.time_zone(), units).await
+}
+
+/// Returns the URL of the compact forecast.
+fn url(coordinates: Coordinates) -> Result<String<128>, Error> {
+    format!(
+        "https://api.met.no/weatherapi/locationforecast/2.0/compact?lat={:.4}&lon={:.4}",
+        coordinates.latitude, coordinates.longitude
+    )
+    .map_err(|_| Error::Http("weather URL too long"))
+}
+
+async fn parse_hourly<const HOURS: usize, R: Read>(
+    reader: R,
+    timezone: &TimeZone,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error> {
+    let mut forecast = heapless::Vec::new();
+
+    time_steps(reader, |step| {
+        // Only the hourly part of the time series carries a one hour summary.
+        let Some(period) = step.data.next_1_hours else {
+            return false;
+        };
+
+        forecast
+            .push(HourlyForecast {
+                time: step.time.to_zoned(timezone.clone()).datetime(),
+                temperature: units
+                    .temperature
+                    .from_celsius(step.data.instant.details.air_temperature),
+                weather_code: weather_code(period.summary.symbol_code),
+            })
+            .is_ok()
+    })
+    .await?;
+
+    Ok(forecast)
+}
+
+async fn parse_daily<const DAYS: usize, R: Read>(
+    reader: R,
+    timezone: &TimeZone,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error> {
+    let mut days = Days::new();
+
+    time_steps(reader, |step| {
+        let Some(weather_code) = step.weather_code() else {
+            return true;
+        };
+
+        let date = step.time.to_zoned(timezone.clone()).date();
+        days.add(
+            date,
+            units
+                .temperature
+                .from_celsius(step.data.instant.details.air_temperature),
+            weather_code,
+        )
+    })
+    .await?;
+
+    Ok(days.days)
+}
+
+/// Pass each time step of the compact forecast read from `reader` to `f` until it returns
+/// `false`.
+async fn time_steps<R, F>(reader: R, mut f: F) -> Result<(), Error>
+where
+    R: Read,
+    F: FnMut(TimeStep<'_>) -> bool,
+{
+    let mut record_buffer = [0u8; MAX_RECORD_SIZE];
+
+    let mut json = Tokenizer::<_>::new(reader);
+    json.seek_key("timeseries").await?;
+    json.begin_array().await?;
+
+    while let Some(bytes) = json.raw_value(&mut record_buffer).await? {
+        let (step, _) = serde_json_core::from_slice::<TimeStep>(bytes)
+            .map_err(|_| Error::ParseJson("failed to parse MET Norway time step"))?;
+
+        if !f(step) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &[u8] = include_bytes!("../../fixtures/met_norway.json");
+
+    fn timezone() -> TimeZone {
+        TimeZone::fixed(jiff::tz::offset(1))
+    }
+
+    #[test]
+    fn parses_hourly_forecast() {
+        let forecast =
+            embassy_futures::block_on(parse_hourly::<8, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+        let time = |day, hour| jiff::civil::date(2025, 1, day).at(hour, 0, 0, 0);
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| (forecast.time, forecast.temperature, forecast.weather_code))
+            .collect();
+
+        // Ends with the first six hourly time step.
+        assert_eq!(
+            forecast,
+            [
+                (time(15, 22), -0.4, WeatherCode::Overcast),
+                (time(15, 23), -1.1, WeatherCode::Clear),
+                (time(16, 0), -1.8, WeatherCode::SlightSnowShower),
+                (time(16, 1), -2.0, WeatherCode::SlightThunderstorm),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregates_daily_forecast() {
+        let forecast =
+            embassy_futures::block_on(parse_daily::<3, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| {
+                (
+                    forecast.date,
+                    forecast.min_temperature,
+                    forecast.max_temperature,
+                    forecast.weather_code,
+                )
+            })
+            .collect();
+
+        // The last time step of the 16th has no summary and is skipped.
+        assert_eq!(
+            forecast,
+            [
+                (
+                    jiff::civil::date(2025, 1, 15),
+                    -1.1,
+                    -0.4,
+                    WeatherCode::Overcast
+                ),
+                (
+                    jiff::civil::date(2025, 1, 16),
+                    -2.0,
+                    3.2,
+                    WeatherCode::SlightThunderstorm
+                ),
+                (jiff::civil::date(2025, 1, 17), 0.1, 0.1, WeatherCode::Fog),
+            ]
+        );
+
+        let forecast =
+            embassy_futures::block_on(parse_daily::<2, _>(RESPONSE, &timezone(), &Units::METRIC))
+                .unwrap();
+
+        assert_eq!(forecast.len(), 2);
+    }
+
+    #[test]
+    fn maps_symbols_to_weather_codes() {
+        for (symbol_code, code) in [
+            ("clearsky_day", WeatherCode::Clear),
+            ("clearsky_night", WeatherCode::Clear),
+            ("clearsky_polartwilight", WeatherCode::Clear),
+            ("fair_day", WeatherCode::MainlyClear),
+            ("partlycloudy_night", WeatherCode::PartlyCloudy),
+            ("cloudy", WeatherCode::Overcast),
+            ("fog", WeatherCode::Fog),
+            ("lightrain", WeatherCode::SlightRain),
+            ("rain", WeatherCode::ModerateRain),
+            ("heavyrain", WeatherCode::HeavyRain),
+            ("lightrainshowers_day", WeatherCode::SlightRainShower),
+            ("rainshowers_night", WeatherCode::ModerateRainShower),
+            ("heavyrainshowers_day", WeatherCode::ViolentRainShower),
+            ("lightsleet", WeatherCode::FreezingLightRain),
+            ("sleet", WeatherCode::FreezingLightRain),
+            ("lightsleetshowers_day", WeatherCode::FreezingLightRain),
+            ("sleetshowers_night", WeatherCode::FreezingLightRain),
+            ("heavysleet", WeatherCode::FreezingHeavyRain),
+            ("heavysleetshowers_day", WeatherCode::FreezingHeavyRain),
+            ("lightsnow", WeatherCode::SlightSnow),
+            ("snow", WeatherCode::ModerateSnow),
+            ("heavysnow", WeatherCode::HeavySnow),
+            ("lightsnowshowers_day", WeatherCode::SlightSnowShower),
+            ("snowshowers_night", WeatherCode::SlightSnowShower),
+            ("heavysnowshowers_day", WeatherCode::HeavySnowShower),
+            ("rainandthunder", WeatherCode::SlightThunderstorm),
+            (
+                "heavysleetshowersandthunder_night",
+                WeatherCode::SlightThunderstorm,
+            ),
+            ("sandstorm", WeatherCode::Unknown(u8::MAX)),
+            ("", WeatherCode::Unknown(u8::MAX)),
+        ] {
+            assert_eq!(weather_code(symbol_code), code, "{symbol_code}");
+        }
+    }
+
+    #[test]
+    fn rejects_time_steps_exceeding_buffer() {
+        let step = |padding| {
+            std::format!(
+                "{{\"time\":\"2025-01-15T21:00:00Z\",\"data\":{{\"instant\":{{\"details\":{{\"air_temperature\":1.0}}}},\
+                \"next_1_hours\":{{\"summary\":{{\"symbol_code\":\"fog\"}}}}}},\"padding\":\"{}\"}}",
+                "x".repeat(padding)
+            )
+        };
+        let parse = |step: &str| {
+            let response = std::format!("{{\"properties\":{{\"timeseries\":[{step}]}}}}");
+
+            embassy_futures::block_on(parse_hourly::<8, _>(
+                response.as_bytes(),
+                &timezone(),
+                &Units::METRIC,
+            ))
+        };
+
+        let fitting = step(MAX_RECORD_SIZE - step(0).len());
+        assert_eq!(fitting.len(), MAX_RECORD_SIZE);
+        assert_eq!(parse(&fitting).unwrap().len(), 1);
+
+        let exceeding = step(MAX_RECORD_SIZE + 1 - step(0).len());
+        assert!(parse(&exceeding).is_err());
+    }
+}
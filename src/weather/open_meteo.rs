@@ -0,0 +1,319 @@
+//! Forecasts from the [Open-Meteo](https://open-meteo.com) API.
+
+use embedded_io_async::Read;
+use embedded_nal_async::{Dns, TcpConnect};
+use heapless::{String, format};
+use reqwless::{client::HttpClient, request::Method};
+
+use super::{Coordinates, DailyForecast, HourlyForecast, WeatherCode};
+use crate::errors::Error;
+use crate::json::Tokenizer;
+use crate::units::Units;
+
+pub(super) async fn hourly_forecast<const HOURS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    units: &Units,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url: String<384> = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={:.4}&longitude={:.4}&hourly=temperature_2m,weather_code&timezone=Europe%2FBerlin&forecast_days={}&temperature_unit={}&wind_speed_unit={}&precipitation_unit={}",
+        coordinates.latitude,
+        coordinates.longitude,
+        HOURS.div_ceil(24),
+        units.temperature.query_value(),
+        units.wind_speed.query_value(),
+        units.precipitation.query_value()
+    )
+    .map_err(|_| Error::Http("weather URL too long"))?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_hourly(response.body().reader()).await
+}
+
+pub(super) async fn daily_forecast<const DAYS: usize, T, D>(
+    client: &mut HttpClient<'_, T, D>,
+    coordinates: Coordinates,
+    units: &Units,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error>
+where
+    T: TcpConnect,
+    D: Dns,
+{
+    let url: String<384> = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={:.4}&longitude={:.4}&daily=weather_code,temperature_2m_max,temperature_2m_min,apparent_temperature_max,apparent_temperature_min,uv_index_max&timezone=Europe%2FBerlin&forecast_days={DAYS}&temperature_unit={}&wind_speed_unit={}&precipitation_unit={}",
+        coordinates.latitude,
+        coordinates.longitude,
+        units.temperature.query_value(),
+        units.wind_speed.query_value(),
+        units.precipitation.query_value()
+    )
+    .map_err(|_| Error::Http("weather URL too long"))?;
+
+    let mut write_buffer = [0u8; 1024];
+
+    let mut request = client
+        .request(Method::GET, &url)
+        .await
+        .map_err(|_| Error::Http("failed to connect to weather URL"))?;
+
+    let response = request
+        .send(&mut write_buffer)
+        .await
+        .map_err(|_| Error::Http("failed to send request"))?;
+
+    parse_daily(response.body().reader()).await
+}
+
+async fn parse_hourly<const HOURS: usize, R: Read>(
+    reader: R,
+) -> Result<heapless::Vec<HourlyForecast, HOURS>, Error> {
+    let mut time = [jiff::civil::DateTime::default(); HOURS];
+    let mut temperature = [0.0; HOURS];
+    let mut weather_code = [WeatherCode::Clear; HOURS];
+    let mut times = 0;
+    let mut temperatures = 0;
+    let mut weather_codes = 0;
+
+    let mut json = Tokenizer::<_>::new(reader);
+    json.seek_key("hourly").await?;
+    json.begin_object().await?;
+
+    while let Some(key) = json.next_key().await? {
+        match key {
+            "time" => {
+                times = json
+                    .read_array(&mut time, |token| {
+                        token
+                            .as_str()?
+                            .parse()
+                            .map_err(|_| Error::DateTime("failed to parse forecast time"))
+                    })
+                    .await?
+            }
+            "temperature_2m" => {
+                temperatures = json
+                    .read_array(&mut temperature, |token| token.as_f32())
+                    .await?
+            }
+            "weather_code" => {
+                weather_codes = json
+                    .read_array(&mut weather_code, |token| {
+                        token.as_u8().map(WeatherCode::from)
+                    })
+                    .await?
+            }
+            _ => json.skip_value().await?,
+        }
+    }
+
+    let forecast = time
+        .into_iter()
+        .zip(temperature)
+        .zip(weather_code)
+        .take(times.min(temperatures).min(weather_codes))
+        .map(|((time, temperature), weather_code)| HourlyForecast {
+            time,
+            temperature,
+            weather_code,
+        })
+        .collect();
+
+    Ok(forecast)
+}
+
+async fn parse_daily<const DAYS: usize, R: Read>(
+    reader: R,
+) -> Result<heapless::Vec<DailyForecast, DAYS>, Error> {
+    let mut date = [jiff::civil::Date::default(); DAYS];
+    let mut min_temperature = [0.0; DAYS];
+    let mut max_temperature = [0.0; DAYS];
+    let mut apparent_min_temperature = [f32::NAN; DAYS];
+    let mut apparent_max_temperature = [f32::NAN; DAYS];
+    let mut uv_index_max = [f32::NAN; DAYS];
+    let mut weather_code = [WeatherCode::Clear; DAYS];
+    let mut dates = 0;
+    let mut min_temperatures = 0;
+    let mut max_temperatures = 0;
+    let mut weather_codes = 0;
+
+    let mut json = Tokenizer::<_>::new(reader);
+    json.seek_key("daily").await?;
+    json.begin_object().await?;
+
+    while let Some(key) = json.next_key().await? {
+        match key {
+            "time" => {
+                dates = json
+                    .read_array(&mut date, |token| {
+                        token
+                            .as_str()?
+                            .parse()
+                            .map_err(|_| Error::DateTime("failed to parse forecast date"))
+                    })
+                    .await?
+            }
+            "temperature_2m_min" => {
+                min_temperatures = json
+                    .read_array(&mut min_temperature, |token| token.as_f32())
+                    .await?
+            }
+            "temperature_2m_max" => {
+                max_temperatures = json
+                    .read_array(&mut max_temperature, |token| token.as_f32())
+                    .await?
+            }
+            "apparent_temperature_min" => {
+                json.read_array(&mut apparent_min_temperature, |token| token.as_f32())
+                    .await?;
+            }
+            "apparent_temperature_max" => {
+                json.read_array(&mut apparent_max_temperature, |token| token.as_f32())
+                    .await?;
+            }
+            "uv_index_max" => {
+                json.read_array(&mut uv_index_max, |token| token.as_f32())
+                    .await?;
+            }
+            "weather_code" => {
+                weather_codes = json
+                    .read_array(&mut weather_code, |token| {
+                        token.as_u8().map(WeatherCode::from)
+                    })
+                    .await?
+            }
+            _ => json.skip_value().await?,
+        }
+    }
+
+    let count = dates
+        .min(min_temperatures)
+        .min(max_temperatures)
+        .min(weather_codes);
+
+    // Optional values stay NaN if missing or null.
+    let known = |value: f32| (!value.is_nan()).then_some(value);
+
+    let forecast = (0..count)
+        .map(|index| DailyForecast {
+            date: date[index],
+            min_temperature: min_temperature[index],
+            max_temperature: max_temperature[index],
+            apparent_min_temperature: known(apparent_min_temperature[index]),
+            apparent_max_temperature: known(apparent_max_temperature[index]),
+            uv_index_max: known(uv_index_max[index]),
+            weather_code: weather_code[index],
+        })
+        .collect();
+
+    Ok(forecast)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &[u8] = include_bytes!("../../fixtures/open_meteo.json");
+
+    #[test]
+    fn parses_hourly_forecast() {
+        let forecast = embassy_futures::block_on(parse_hourly::<8, _>(RESPONSE)).unwrap();
+        let hour = |hour| jiff::civil::date(2025, 1, 15).at(hour, 0, 0, 0);
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| (forecast.time, forecast.temperature, forecast.weather_code))
+            .collect();
+
+        assert_eq!(
+            forecast,
+            [
+                (hour(0), -0.4, WeatherCode::Overcast),
+                (hour(1), -0.9, WeatherCode::Fog),
+                (hour(2), -1.3, WeatherCode::SlightSnow),
+                (hour(3), -1.2, WeatherCode::PartlyCloudy),
+            ]
+        );
+
+        let forecast = embassy_futures::block_on(parse_hourly::<2, _>(RESPONSE)).unwrap();
+
+        assert_eq!(forecast.len(), 2);
+        assert_eq!(forecast[1].time, hour(1));
+    }
+
+    #[test]
+    fn parses_daily_forecast() {
+        let forecast = embassy_futures::block_on(parse_daily::<8, _>(RESPONSE)).unwrap();
+
+        let forecast: Vec<_> = forecast
+            .iter()
+            .map(|forecast| {
+                (
+                    forecast.date,
+                    forecast.min_temperature,
+                    forecast.max_temperature,
+                    forecast.apparent_min_temperature,
+                    forecast.apparent_max_temperature,
+                    forecast.uv_index_max,
+                    forecast.weather_code,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            forecast,
+            [
+                (
+                    jiff::civil::date(2025, 1, 15),
+                    -1.3,
+                    2.1,
+                    Some(-5.1),
+                    Some(-1.5),
+                    Some(0.85),
+                    WeatherCode::SlightSnow
+                ),
+                (
+                    jiff::civil::date(2025, 1, 16),
+                    -2.0,
+                    3.4,
+                    Some(-6.0),
+                    Some(-0.2),
+                    Some(1.1),
+                    WeatherCode::Overcast
+                ),
+                (
+                    jiff::civil::date(2025, 1, 17),
+                    0.8,
+                    5.0,
+                    None,
+                    None,
+                    None,
+                    WeatherCode::SlightRain
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_responses() {
+        let parse = |response: &[u8]| embassy_futures::block_on(parse_hourly::<8, _>(response));
+
+        assert!(parse(b"{\"error\":true,\"reason\":\"Latitude must be in range\"}").is_err());
+        assert!(parse(b"{\"hourly\":{\"time\":[\"yesterday\"]}}").is_err());
+        assert!(parse(b"{\"hourly\":{\"weather_code\":[300]}}").is_err());
+    }
+}
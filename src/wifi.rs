@@ -1,11 +1,95 @@
+extern crate alloc;
+
+use core::cell::RefCell;
+use core::cmp::Reverse;
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use embassy_futures::select::{Either, select};
 use embassy_time::{Duration, Timer};
-use esp_radio::wifi::{ClientConfig, ModeConfig, WifiController, WifiEvent, WifiStaState};
+use esp_radio::wifi::{
+    AccessPointInfo, ClientConfig, ModeConfig, ScanConfig, WifiController, WifiError, WifiEvent,
+    WifiStaState,
+};
+
+use crate::config::{MAX_NETWORKS, Network};
+
+/// Interval in which the signal strength is updated while connected.
+const SIGNAL_STRENGTH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of failed attempts to connect in a row after which [`keep_connection`] gives up.
+const MAX_CONNECT_ATTEMPTS: u32 = 10;
+
+/// Number of failed attempts to connect to a network in a row after which other known networks
+/// are tried first.
+const MAX_NETWORK_ATTEMPTS: u32 = 3;
+
+/// Signal strength of the connected access point, updated by [`keep_connection`].
+#[derive(Clone, Default)]
+pub struct SignalStrength {
+    rssi: Rc<RefCell<Option<i32>>>,
+}
+
+impl SignalStrength {
+    /// Return the RSSI in dBm or `None` if not connected.
+    pub fn rssi(&self) -> Option<i32> {
+        *self.rssi.borrow()
+    }
+
+    fn set(&self, rssi: Option<i32>) {
+        *self.rssi.borrow_mut() = rssi;
+    }
+}
+
+/// Returns the access points in range, strongest first and only the strongest one of each network.
+pub async fn scan(controller: &mut WifiController<'_>) -> Result<Vec<AccessPointInfo>, WifiError> {
+    let mut access_points = controller
+        .scan_with_config_async(ScanConfig::default())
+        .await?;
+
+    // Mesh networks announce the same name from several access points.
+    access_points.sort_by(|a, b| {
+        a.ssid
+            .cmp(&b.ssid)
+            .then(b.signal_strength.cmp(&a.signal_strength))
+    });
+    access_points.dedup_by(|a, b| a.ssid == b.ssid);
+
+    access_points.sort_by_key(|access_point| Reverse(access_point.signal_strength));
+
+    Ok(access_points)
+}
+
+/// Connects to the strongest of the known `networks` in range and reconnects whenever the
+/// connection drops. A network that fails to connect [`MAX_NETWORK_ATTEMPTS`] times in a row is
+/// skipped in favor of the next one. Returns the controller after [`MAX_CONNECT_ATTEMPTS`] failed
+/// attempts to connect in a row.
+pub async fn keep_connection<'d>(
+    mut controller: WifiController<'d>,
+    networks: &[Network],
+    signal_strength: SignalStrength,
+) -> WifiController<'d> {
+    let mut failed_attempts = 0;
+    // Failed attempts to connect in a row, by network.
+    let mut network_failures = [0; MAX_NETWORKS];
 
-pub async fn keep_connection(mut controller: WifiController<'_>, ssid: &str, password: &str) {
     loop {
         if matches!(esp_radio::wifi::sta_state(), WifiStaState::Connected) {
             log::info!("connected to Wi-Fi");
-            controller.wait_for_event(WifiEvent::StaDisconnected).await;
+
+            while matches!(esp_radio::wifi::sta_state(), WifiStaState::Connected) {
+                signal_strength.set(controller.rssi().ok());
+
+                let disconnected = controller.wait_for_event(WifiEvent::StaDisconnected);
+
+                if let Either::First(()) =
+                    select(disconnected, Timer::after(SIGNAL_STRENGTH_INTERVAL)).await
+                {
+                    break;
+                }
+            }
+
+            signal_strength.set(None);
 
             log::warn!("disconnected from Wi-Fi");
             Timer::after(Duration::from_secs(5)).await;
@@ -16,26 +100,81 @@ pub async fn keep_connection(mut controller: WifiController<'_>, ssid: &str, pas
         };
 
         if !is_started {
-            let config = ModeConfig::Client(
-                ClientConfig::default()
-                    .with_ssid(ssid.into())
-                    .with_password(password.into()),
-            );
-
             controller
-                .set_config(&config)
+                .set_config(&ModeConfig::Client(ClientConfig::default()))
                 .expect("setting Wi-Fi client config");
 
             log::info!("starting Wi-Fi");
             controller.start_async().await.expect("starting Wi-Fi");
         }
 
+        let in_range = scan(&mut controller)
+            .await
+            .inspect_err(|err| log::warn!("failed to scan for Wi-Fi networks: {err:?}"))
+            .unwrap_or_default();
+
+        let Some(index) = choose(networks, &in_range, &mut network_failures) else {
+            return controller;
+        };
+
+        let network = &networks[index];
+
+        let config = ModeConfig::Client(
+            ClientConfig::default()
+                .with_ssid(network.ssid.as_str().into())
+                .with_password(network.password.as_str().into()),
+        );
+
+        controller
+            .set_config(&config)
+            .expect("setting Wi-Fi client config");
+
         match controller.connect_async().await {
-            Ok(()) => log::info!("connected to {ssid}"),
+            Ok(()) => {
+                log::info!("connected to {}", network.ssid);
+                failed_attempts = 0;
+                network_failures[index] = 0;
+            }
             Err(err) => {
-                log::error!("failed to connect to Wi-Fi: {err:?}");
+                log::error!("failed to connect to {}: {err:?}", network.ssid);
+                failed_attempts += 1;
+                network_failures[index] += 1;
+
+                if failed_attempts >= MAX_CONNECT_ATTEMPTS {
+                    return controller;
+                }
+
                 Timer::after(Duration::from_secs(5)).await
             }
         }
     }
 }
+
+/// Returns the index of the network in `networks` to connect to: the strongest one in range that
+/// did not fail too often, followed by the ones not found in the scan such as hidden networks.
+/// Once all failed too often, they are all tried again.
+fn choose(
+    networks: &[Network],
+    in_range: &[AccessPointInfo],
+    network_failures: &mut [u32; MAX_NETWORKS],
+) -> Option<usize> {
+    let mut strongest_first = in_range
+        .iter()
+        .filter_map(|access_point| {
+            networks
+                .iter()
+                .position(|network| network.ssid == access_point.ssid.as_str())
+        })
+        .chain(0..networks.len());
+
+    let usable = |index: &usize| network_failures[*index] < MAX_NETWORK_ATTEMPTS;
+
+    if let Some(index) = strongest_first.clone().find(usable) {
+        return Some(index);
+    }
+
+    log::warn!("all known Wi-Fi networks failed to connect, trying them all again");
+    network_failures.fill(0);
+
+    strongest_first.next()
+}